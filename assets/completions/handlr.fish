@@ -1,6 +1,6 @@
 function __handlr_autocomplete
   function subcommands
-    set -l handlr_commands 'get help launch list open set unset mime'
+    set -l handlr_commands 'get help launch list open set unset mime config terminal'
     complete -f -c handlr -n "not __fish_seen_subcommand_from $handlr_commands" -a "get" -d "Show handler for mime"
     complete -f -c handlr -n "not __fish_seen_subcommand_from $handlr_commands" -a "launch" -d "Launch given handler with path/args"
     complete -f -c handlr -n "not __fish_seen_subcommand_from $handlr_commands" -a "list" -d "Show handlers (default applications)"
@@ -8,6 +8,8 @@ function __handlr_autocomplete
     complete -f -c handlr -n "not __fish_seen_subcommand_from $handlr_commands" -a "set" -d "Set handler for extension (e.g. pdf) or mime type"
     complete -f -c handlr -n "not __fish_seen_subcommand_from $handlr_commands" -a "unset" -d "Unset handler"
     complete -f -c handlr -n "not __fish_seen_subcommand_from $handlr_commands" -a "mime" -d "Get mimetype of path/URL"
+    complete -f -c handlr -n "not __fish_seen_subcommand_from $handlr_commands" -a "config" -d "Inspect and change handlr's configuration"
+    complete -f -c handlr -n "not __fish_seen_subcommand_from $handlr_commands" -a "terminal" -d "Inspect and choose the terminal emulator"
   end
 
   function _set_add
@@ -22,8 +24,19 @@ function __handlr_autocomplete
   _set_add
   complete -f -c handlr -n '__fish_seen_subcommand_from get' -a '(handlr autocomplete -m)'
   complete -f -c handlr -n '__fish_seen_subcommand_from get' -l 'json'
+  complete -c handlr -n '__fish_seen_subcommand_from open' -l 'with-menu' -d 'Select from every capable application'
+  complete -c handlr -n '__fish_seen_subcommand_from open mime' -l 'stdin' -d 'Read paths/URLs from stdin'
+  complete -c handlr -n '__fish_seen_subcommand_from open mime' -s '0' -l 'null' -d 'Paths/URLs from stdin are separated by NUL'
+  complete -c handlr -n '__fish_seen_subcommand_from open get launch' -l 'select' -d 'Select a handler even if disabled in the config'
+  complete -c handlr -n '__fish_seen_subcommand_from open get launch' -l 'no-select' -d 'Use the default handler even if the selector is enabled'
   complete -f -c handlr -n '__fish_seen_subcommand_from unset' -a '(handlr autocomplete -m)'
   complete -f -c handlr -n '__fish_seen_subcommand_from launch; __fish_prev_arg_in launch' -a '(handlr autocomplete -m)'
+  complete -f -c handlr -n '__fish_seen_subcommand_from config; __fish_prev_arg_in config' -a 'path show get set edit trust check'
+  complete -f -c handlr -n '__fish_seen_subcommand_from config; __fish_seen_subcommand_from path show get check' -l 'json'
+  complete -f -c handlr -n '__fish_seen_subcommand_from terminal; __fish_prev_arg_in terminal' -a 'get detect set'
+  complete -f -c handlr -n '__fish_seen_subcommand_from terminal; __fish_seen_subcommand_from get detect' -l 'json'
+  complete -f -c handlr -n '__fish_seen_subcommand_from terminal; __fish_prev_arg_in set' -a '(handlr autocomplete -d)'
+  complete -c handlr -l 'config' -r -d 'Use this config file'
 
 end
 
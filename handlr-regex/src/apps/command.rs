@@ -0,0 +1,205 @@
+use super::conditions::Conditions;
+use crate::{
+    common::{DesktopEntry, ExecMode},
+    error::Result,
+    multiplexer::MultiplexerMode,
+    CONFIG,
+};
+use mime::Mime;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, HashMap},
+    ffi::OsString,
+    str::FromStr,
+};
+
+// used for deserializing from config file
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct ConfigMimeHandler {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    name: Option<String>,
+    exec: String,
+    #[serde(default)]
+    terminal: bool,
+    // where to open the handler when running inside a multiplexer
+    // overrides the multiplexer setting of the config
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    multiplexer: Option<MultiplexerMode>,
+    // may contain wildcards like video/*
+    mimes: Vec<String>,
+    // context the handler applies in
+    #[serde(default, skip_serializing_if = "Conditions::is_empty")]
+    when: Conditions,
+}
+
+impl ConfigMimeHandler {
+    // convert to CommandHandler and the mimes it handles
+    pub(crate) fn compile(&self) -> Result<(Vec<Mime>, CommandHandler)> {
+        Ok((
+            self.mimes
+                .iter()
+                .map(|mime| Mime::from_str(mime))
+                .collect::<Result<_, _>>()?,
+            CommandHandler {
+                name: self.name.clone().unwrap_or_else(|| self.exec.clone()),
+                exec: self.exec.clone(),
+                terminal: self.terminal,
+                multiplexer: self.multiplexer,
+                when: self.when.clone(),
+            },
+        ))
+    }
+}
+
+// A handler that runs a command from the config file
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CommandHandler {
+    name: String,
+    exec: String,
+    terminal: bool,
+    multiplexer: Option<MultiplexerMode>,
+    when: Conditions,
+}
+
+impl CommandHandler {
+    pub fn new(name: String, exec: String, terminal: bool) -> Self {
+        CommandHandler {
+            name,
+            exec,
+            terminal,
+            multiplexer: None,
+            when: Conditions::default(),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    // same kludge as RegexHandler to reuse the program execution code
+    pub fn get_entry(&self) -> DesktopEntry {
+        DesktopEntry {
+            name: self.name.clone(),
+            exec: self.exec.clone(),
+            file_name: OsString::from(""),
+            terminal: self.terminal,
+            multiplexer: self.multiplexer,
+            icon: None,
+            mimes: Vec::new(),
+            categories: HashMap::new(),
+        }
+    }
+
+    pub fn launch(&self, args: Vec<String>) -> Result<()> {
+        self.get_entry().exec(ExecMode::Launch, args)
+    }
+
+    pub fn open(&self, args: Vec<String>) -> Result<()> {
+        self.get_entry().exec(ExecMode::Open, args)
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct CommandApps(Vec<(Vec<Mime>, CommandHandler)>);
+
+impl CommandApps {
+    // convert Config's ConfigMimeHandlers
+    pub fn populate() -> Self {
+        CommandApps(
+            CONFIG
+                .mime_handlers
+                .iter()
+                .enumerate()
+                .filter_map(|(i, handler)| match handler.compile() {
+                    Ok(handler) => Some(handler),
+                    Err(e) => {
+                        eprintln!("ignoring mime_handlers.{}: {}", i, e);
+                        None
+                    }
+                })
+                .collect(),
+        )
+    }
+
+    // convert Config's tty_handlers, which run in the current terminal
+    pub fn populate_tty() -> Self {
+        Self::from_tty_handlers(&CONFIG.tty_handlers)
+    }
+
+    pub(crate) fn from_tty_handlers(
+        handlers: &BTreeMap<String, String>,
+    ) -> Self {
+        CommandApps(
+            handlers
+                .iter()
+                .filter_map(|(mime, exec)| {
+                    Some((
+                        vec![Mime::from_str(mime).ok()?],
+                        CommandHandler::new(exec.clone(), exec.clone(), true),
+                    ))
+                })
+                .collect(),
+        )
+    }
+
+    // get handlers set for exactly this mime, which may be a wildcard
+    pub fn get_handlers(&self, mime: &Mime) -> Vec<CommandHandler> {
+        self.0
+            .iter()
+            .filter(|(mimes, handler)| {
+                mimes.iter().any(|m| m.essence_str() == mime.essence_str())
+                    && handler.when.is_met()
+            })
+            .map(|(_, handler)| handler.clone())
+            .collect()
+    }
+
+    // all mimes with command handlers and the names of their handlers
+    pub fn names(&self) -> HashMap<Mime, Vec<String>> {
+        let mut map = HashMap::<Mime, Vec<String>>::new();
+        for (mimes, handler) in self.0.iter() {
+            for mime in mimes {
+                map.entry(mime.clone())
+                    .or_default()
+                    .push(handler.name.clone());
+            }
+        }
+        map
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn command_handlers() -> Result<()> {
+        let handler = |name: &str, mimes: &[&str]| ConfigMimeHandler {
+            name: Some(name.to_owned()),
+            exec: format!("{} %f", name),
+            terminal: false,
+            multiplexer: None,
+            mimes: mimes.iter().map(|m| m.to_string()).collect(),
+            when: Conditions::default(),
+        };
+
+        let apps = CommandApps(vec![
+            handler("zathura", &["application/pdf", "image/*"]).compile()?,
+            handler("feh", &["image/png"]).compile()?,
+        ]);
+
+        let names = |mime: &str| {
+            apps.get_handlers(&Mime::from_str(mime).unwrap())
+                .into_iter()
+                .map(|h| h.name)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(names("application/pdf"), vec!["zathura"]);
+        assert_eq!(names("image/png"), vec!["feh"]);
+        assert_eq!(names("image/*"), vec!["zathura"]);
+        assert!(names("image/jpeg").is_empty());
+
+        Ok(())
+    }
+}
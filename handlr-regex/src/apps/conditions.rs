@@ -0,0 +1,140 @@
+use serde::{Deserialize, Serialize};
+
+// Context a handler applies in, from the `when` table of a handler
+// Every non-empty condition has to be met
+#[derive(
+    Debug, Clone, Default, PartialEq, Eq, Hash, Deserialize, Serialize,
+)]
+#[serde(default)]
+pub struct Conditions {
+    // all of these have to hold:
+    // `VAR` is set and not empty, `VAR=value` has that value and `!VAR` is unset or empty
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    env: Vec<String>,
+    // any of these is in $XDG_CURRENT_DESKTOP
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    desktop: Vec<String>,
+    // any of these is the hostname
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    hostname: Vec<String>,
+    // any of these is $XDG_SESSION_TYPE, i.e. x11, wayland or tty
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    session_type: Vec<String>,
+}
+
+impl Conditions {
+    pub fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
+
+    pub fn is_met(&self) -> bool {
+        self.is_empty()
+            || self.is_met_with(|name| std::env::var(name).ok(), hostname)
+    }
+
+    fn is_met_with<E, H>(&self, var: E, hostname: H) -> bool
+    where
+        E: Fn(&str) -> Option<String>,
+        H: FnOnce() -> Option<String>,
+    {
+        let is_set = |name: &str| var(name).is_some_and(|v| !v.is_empty());
+
+        self.env.iter().all(|condition| {
+            if let Some(name) = condition.strip_prefix('!') {
+                !is_set(name)
+            } else if let Some((name, value)) = condition.split_once('=') {
+                var(name).as_deref() == Some(value)
+            } else {
+                is_set(condition)
+            }
+        }) && (self.desktop.is_empty()
+            || var("XDG_CURRENT_DESKTOP").is_some_and(|desktops| {
+                desktops.split(':').any(|desktop| {
+                    self.desktop.iter().any(|d| d.eq_ignore_ascii_case(desktop))
+                })
+            }))
+            && (self.session_type.is_empty()
+                || var("XDG_SESSION_TYPE").is_some_and(|session| {
+                    self.session_type
+                        .iter()
+                        .any(|s| s.eq_ignore_ascii_case(&session))
+                }))
+            && (self.hostname.is_empty()
+                || hostname().is_some_and(|hostname| {
+                    self.hostname
+                        .iter()
+                        .any(|h| h.eq_ignore_ascii_case(&hostname))
+                }))
+    }
+}
+
+fn hostname() -> Option<String> {
+    std::fs::read_to_string("/proc/sys/kernel/hostname")
+        .or_else(|_| std::fs::read_to_string("/etc/hostname"))
+        .ok()
+        .or_else(|| std::env::var("HOSTNAME").ok())
+        .map(|hostname| hostname.trim().to_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn conditions() {
+        let vars = [
+            ("XDG_CURRENT_DESKTOP", "ubuntu:GNOME"),
+            ("XDG_SESSION_TYPE", "wayland"),
+            ("SSH_CONNECTION", "1.2.3.4 22 5.6.7.8 22"),
+            ("WORK", "1"),
+            ("EMPTY", ""),
+        ]
+        .iter()
+        .copied()
+        .collect::<HashMap<_, _>>();
+
+        let is_met = |conditions: Conditions| {
+            conditions.is_met_with(
+                |name| vars.get(name).map(|v| v.to_string()),
+                || Some(String::from("laptop")),
+            )
+        };
+        let list =
+            |items: &[&str]| items.iter().map(|i| i.to_string()).collect();
+
+        assert!(is_met(Conditions::default()));
+
+        assert!(is_met(Conditions {
+            env: list(&["SSH_CONNECTION", "WORK=1", "!EMPTY", "!MISSING"]),
+            ..Default::default()
+        }));
+        assert!(!is_met(Conditions {
+            env: list(&["WORK=0"]),
+            ..Default::default()
+        }));
+        assert!(!is_met(Conditions {
+            env: list(&["EMPTY"]),
+            ..Default::default()
+        }));
+
+        assert!(is_met(Conditions {
+            desktop: list(&["KDE", "gnome"]),
+            session_type: list(&["wayland"]),
+            hostname: list(&["laptop"]),
+            ..Default::default()
+        }));
+        assert!(!is_met(Conditions {
+            desktop: list(&["KDE"]),
+            ..Default::default()
+        }));
+        assert!(!is_met(Conditions {
+            session_type: list(&["x11"]),
+            ..Default::default()
+        }));
+        assert!(!is_met(Conditions {
+            hostname: list(&["desktop"]),
+            ..Default::default()
+        }));
+    }
+}
@@ -0,0 +1,123 @@
+use super::CommandHandler;
+use crate::utils::in_path;
+use mime::Mime;
+
+// Mimes opened with $BROWSER
+static BROWSER_MIMES: &[&str] = &[
+    "x-scheme-handler/http",
+    "x-scheme-handler/https",
+    "text/html",
+    "application/xhtml+xml",
+];
+
+// Browsers in $BROWSER that have to run in a terminal
+static TERMINAL_BROWSERS: &[&str] = &[
+    "w3m", "lynx", "links", "links2", "elinks", "browsh", "cha", "carbonyl",
+];
+
+// Handler from the conventional $BROWSER, $VISUAL and $EDITOR variables
+// Only used when `env_fallbacks` is enabled and nothing else handles the mime
+pub fn env_handler(mime: &Mime) -> Option<CommandHandler> {
+    env_handler_with(mime, |name| std::env::var(name).ok(), in_path)
+}
+
+fn env_handler_with<V, P>(
+    mime: &Mime,
+    var: V,
+    in_path: P,
+) -> Option<CommandHandler>
+where
+    V: Fn(&str) -> Option<String>,
+    P: Fn(&str) -> bool,
+{
+    let var = |name: &str| var(name).filter(|v| !v.trim().is_empty());
+
+    let browser = || {
+        // like $PATH, $BROWSER is a list of commands to try in order
+        // %s is replaced by the URL, which is appended otherwise
+        var("BROWSER")?
+            .split(':')
+            .map(str::trim)
+            .find_map(|cmd| {
+                let program = shlex::split(cmd)?.into_iter().next()?;
+                in_path(&program).then_some((cmd, program))
+            })
+            .map(|(cmd, program)| {
+                let exec = if cmd.contains("%s") {
+                    cmd.replace("%s", "%u")
+                } else {
+                    format!("{} %u", cmd)
+                };
+                let terminal = std::path::Path::new(&program)
+                    .file_name()
+                    .and_then(|name| name.to_str())
+                    .is_some_and(|name| TERMINAL_BROWSERS.contains(&name));
+                CommandHandler::new(
+                    format!("{} (from $BROWSER)", cmd),
+                    exec,
+                    terminal,
+                )
+            })
+    };
+
+    let editor = || {
+        ["VISUAL", "EDITOR"].iter().find_map(|name| {
+            let cmd = var(name)?;
+            Some(CommandHandler::new(
+                format!("{} (from ${})", cmd, name),
+                format!("{} %f", cmd),
+                true,
+            ))
+        })
+    };
+
+    if BROWSER_MIMES.contains(&mime.essence_str()) {
+        browser()
+            .or_else(|| (mime.type_() == mime::TEXT).then(editor).flatten())
+    } else if mime.type_() == mime::TEXT {
+        editor()
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{collections::HashMap, str::FromStr};
+
+    #[test]
+    fn env_handlers() {
+        let handler = |vars: &[(&str, &str)], mime: &str| {
+            let vars = vars.iter().copied().collect::<HashMap<_, _>>();
+            env_handler_with(
+                &Mime::from_str(mime).unwrap(),
+                |name| vars.get(name).map(|v| v.to_string()),
+                |program| program != "missing-browser",
+            )
+            .map(|h| {
+                let entry = h.get_entry();
+                (entry.exec, entry.terminal)
+            })
+        };
+        let vars = [
+            ("BROWSER", "missing-browser:w3m %s:firefox"),
+            ("EDITOR", "nvim"),
+            ("VISUAL", ""),
+        ];
+
+        assert_eq!(
+            handler(&vars, "x-scheme-handler/https"),
+            Some(("w3m %u".to_owned(), true))
+        );
+        assert_eq!(
+            handler(&[("BROWSER", "/usr/bin/firefox")], "text/html"),
+            Some(("/usr/bin/firefox %u".to_owned(), false))
+        );
+        assert_eq!(
+            handler(&vars, "text/plain"),
+            Some(("nvim %f".to_owned(), true))
+        );
+        assert_eq!(handler(&vars, "image/png"), None);
+    }
+}
@@ -1,7 +1,17 @@
+mod command;
+mod conditions;
+mod env;
 mod regex;
 mod system;
+mod url_rule;
 mod user;
 
-pub use self::regex::{ConfigHandler, RegexApps, RegexHandler};
+pub use self::regex::{
+    ConfigHandler, MatchPath, PathKind, RegexApps, RegexHandler,
+};
+pub use command::{CommandApps, CommandHandler, ConfigMimeHandler};
+pub use conditions::Conditions;
+pub use env::env_handler;
 pub use system::SystemApps;
+pub use url_rule::{ConfigUrlRule, UrlRule};
 pub use user::{MimeApps, Rule as MimeappsRule, APPS};
@@ -1,32 +1,131 @@
+use super::{
+    conditions::Conditions,
+    url_rule::{ConfigUrlRule, UrlRule},
+};
 use crate::{
-    common::{DesktopEntry, ExecMode, UserPath},
+    common::{mime_matches, DesktopEntry, ExecMode, UserPath},
     error::{ErrorKind, Result},
+    multiplexer::MultiplexerMode,
     CONFIG,
 };
+use glob::{MatchOptions, Pattern};
+use mime::Mime;
 use regex::RegexSet;
 use serde::{Deserialize, Serialize};
 use std::{
     collections::HashMap,
     ffi::OsString,
     hash::{Hash, Hasher},
+    path::Path,
+    str::FromStr,
 };
 
+// form of the path that regexes are matched against
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Deserialize, Serialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum MatchPath {
+    // absolute paths and percent-decoded URLs
+    #[default]
+    Normalized,
+    // exactly what was given on the command line
+    Raw,
+}
+
+// kind of path a handler applies to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PathKind {
+    File,
+    Url,
+}
+
+impl PathKind {
+    fn matches(&self, path: &UserPath) -> bool {
+        matches!(
+            (self, path),
+            (PathKind::File, UserPath::File(_))
+                | (PathKind::Url, UserPath::Url(_))
+        )
+    }
+}
+
 // used for deserializing from config file
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Default, Deserialize, Serialize)]
 pub struct ConfigHandler {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    name: Option<String>,
     exec: String,
     #[serde(default)]
     terminal: bool,
+    // where to open the handler when running inside a multiplexer
+    // overrides the multiplexer setting of the config
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    multiplexer: Option<MultiplexerMode>,
+    // handlers with a higher priority are checked first
+    #[serde(default)]
+    priority: i64,
+    // only offer this handler in the selector instead of using it outright
+    #[serde(default)]
+    fallthrough: bool,
+    // conditions checked in addition to the regexes
+    // mimes may contain wildcards like video/*
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    mimes: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    schemes: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    kind: Option<PathKind>,
+    #[serde(default)]
+    match_path: MatchPath,
+    #[serde(default)]
     regexes: Vec<String>,
+    // structured rules for URLs, checked alongside the regexes
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    urls: Vec<ConfigUrlRule>,
+    // globs for local files, checked alongside the regexes
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    globs: Vec<String>,
+    // context the handler applies in
+    #[serde(default, skip_serializing_if = "Conditions::is_empty")]
+    when: Conditions,
 }
 
 impl ConfigHandler {
     // convert to RegexHandler
-    fn compile_regex(&self) -> Result<RegexHandler> {
+    pub(crate) fn compile_regex(&self) -> Result<RegexHandler> {
         Ok(RegexHandler {
+            name: self.name.clone().unwrap_or_else(|| self.exec.clone()),
             exec: self.exec.clone(),
             terminal: self.terminal,
+            multiplexer: self.multiplexer,
+            priority: self.priority,
+            fallthrough: self.fallthrough,
+            mimes: self
+                .mimes
+                .iter()
+                .map(|mime| Mime::from_str(mime))
+                .collect::<Result<_, _>>()?,
+            schemes: self
+                .schemes
+                .iter()
+                .map(|scheme| scheme.to_lowercase())
+                .collect(),
+            kind: self.kind,
+            match_path: self.match_path,
             regexes: HandlerRegexSet::new(self.regexes.clone())?,
+            urls: self
+                .urls
+                .iter()
+                .map(ConfigUrlRule::compile)
+                .collect::<Result<_>>()?,
+            globs: self
+                .globs
+                .iter()
+                .map(|glob| FileGlob::new(glob))
+                .collect::<Result<_>>()?,
+            when: self.when.clone(),
         })
     }
 }
@@ -65,23 +164,90 @@ impl Hash for HandlerRegexSet {
     }
 }
 
+// glob matched against the absolute path of a local file
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct FileGlob {
+    pattern: Pattern,
+    // patterns without a slash only match the file name
+    file_name_only: bool,
+}
+
+impl FileGlob {
+    fn new(glob: &str) -> Result<FileGlob> {
+        Self::with_env(glob, |name| std::env::var(name).ok())
+    }
+
+    // `~` and environment variables are expanded using `var`
+    // Variables that are not set are left as they are
+    fn with_env<V: Fn(&str) -> Option<String>>(
+        glob: &str,
+        var: V,
+    ) -> Result<FileGlob> {
+        let glob = shellexpand::full_with_context_no_errors(
+            glob,
+            || var("HOME"),
+            &var,
+        );
+        let file_name_only = !glob.contains('/');
+
+        // relative patterns with a slash can match anywhere in the path
+        let glob = if file_name_only || glob.starts_with('/') {
+            glob.into_owned()
+        } else {
+            format!("**/{}", glob)
+        };
+
+        Ok(FileGlob {
+            pattern: Pattern::new(&glob)?,
+            file_name_only,
+        })
+    }
+
+    fn is_match(&self, path: &Path) -> bool {
+        let options = MatchOptions {
+            require_literal_separator: true,
+            ..Default::default()
+        };
+
+        if self.file_name_only {
+            path.file_name().is_some_and(|name| {
+                self.pattern.matches_with(&name.to_string_lossy(), options)
+            })
+        } else {
+            self.pattern.matches_path_with(path, options)
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct RegexHandler {
+    name: String,
     exec: String,
     terminal: bool,
+    multiplexer: Option<MultiplexerMode>,
+    priority: i64,
+    fallthrough: bool,
+    mimes: Vec<Mime>,
+    schemes: Vec<String>,
+    kind: Option<PathKind>,
+    match_path: MatchPath,
     regexes: HandlerRegexSet,
+    urls: Vec<UrlRule>,
+    globs: Vec<FileGlob>,
+    when: Conditions,
 }
 
 impl RegexHandler {
     // kludge together a fake DesktopEntry
     // there's probably a better way to avoid reinventing the wheel with the program execution code
-    fn get_entry(&self) -> DesktopEntry {
-        //
+    pub fn get_entry(&self) -> DesktopEntry {
         DesktopEntry {
-            name: String::from(""),
+            name: self.name.clone(),
             exec: self.exec.clone(),
             file_name: OsString::from(""),
             terminal: self.terminal,
+            multiplexer: self.multiplexer,
+            icon: None,
             mimes: Vec::new(),
             categories: HashMap::new(),
         }
@@ -92,13 +258,47 @@ impl RegexHandler {
         self.get_entry().exec(ExecMode::Open, args)
     }
 
-    fn is_match(&self, path: &str) -> bool {
-        self.regexes.is_match(path)
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    // whether this handler should only be offered by the selector
+    pub fn falls_through(&self) -> bool {
+        self.fallthrough
+    }
+
+    fn is_match(&self, path: &UserPath, normalized: &str) -> bool {
+        // content has no path or URL to match, so it is only opened by mime
+        let url = match path {
+            UserPath::Url(url) => Some(url),
+            UserPath::File(_) => None,
+            UserPath::Content(_) => return false,
+        };
+
+        self.kind.is_none_or(|kind| kind.matches(path))
+            && (self.schemes.is_empty() || {
+                let scheme = url.map_or("file", |url| url.scheme());
+                self.schemes.iter().any(|s| s == scheme)
+            })
+            && (self.mimes.is_empty()
+                || path.get_mime().is_ok_and(|mime| {
+                    self.mimes.iter().any(|m| mime_matches(m, &mime))
+                }))
+            && (match self.match_path {
+                MatchPath::Normalized => self.regexes.is_match(normalized),
+                MatchPath::Raw => self.regexes.is_match(&path.to_string()),
+            } || match url {
+                Some(url) => self.urls.iter().any(|rule| rule.is_match(url)),
+                None => self
+                    .globs
+                    .iter()
+                    .any(|glob| glob.is_match(Path::new(normalized))),
+            })
     }
 }
 
 #[derive(Debug, Clone, Default)]
-pub struct RegexApps(Vec<RegexHandler>);
+pub struct RegexApps(pub(crate) Vec<RegexHandler>);
 
 impl RegexApps {
     // convert Config's ConfigHandlers
@@ -107,18 +307,36 @@ impl RegexApps {
             CONFIG
                 .handlers
                 .iter()
-                .filter_map(|handler| handler.compile_regex().ok())
+                .enumerate()
+                .filter_map(|(i, handler)| match handler.compile_regex() {
+                    Ok(handler) => Some(handler),
+                    Err(e) => {
+                        eprintln!("ignoring handlers.{}: {}", i, e);
+                        None
+                    }
+                })
                 .collect(),
         )
     }
-    // get matching handler
-    pub fn get_handler(&self, path: &UserPath) -> Result<RegexHandler> {
-        Ok(self
+    // get all matching handlers, highest priority first
+    // handlers with equal priority keep the order of the config file
+    pub fn get_handlers(&self, path: &UserPath) -> Vec<RegexHandler> {
+        let normalized = path.normalized();
+        let mut handlers = self
             .0
             .iter()
-            .find(|app| app.is_match(&path.to_string()))
-            .ok_or_else(|| ErrorKind::NotFound(path.to_string()))?
-            .clone())
+            .filter(|app| app.when.is_met() && app.is_match(path, &normalized))
+            .cloned()
+            .collect::<Vec<_>>();
+        handlers.sort_by_key(|app| std::cmp::Reverse(app.priority));
+        handlers
+    }
+    // get matching handler that does not fall through
+    pub fn get_handler(&self, path: &UserPath) -> Result<RegexHandler> {
+        self.get_handlers(path)
+            .into_iter()
+            .find(|app| !app.fallthrough)
+            .ok_or_else(|| ErrorKind::NotFound(path.to_string()).into())
     }
 }
 
@@ -135,8 +353,8 @@ mod tests {
 
         let config_handler = ConfigHandler {
             exec: String::from(exec),
-            terminal: false,
             regexes: regexes.to_owned(),
+            ..Default::default()
         };
 
         let regex_handler = config_handler
@@ -144,10 +362,21 @@ mod tests {
             .expect("ConfigHandler::compile_regex() returned Err");
 
         let expected_regex_handler = RegexHandler {
+            name: String::from(exec),
             exec: String::from(exec),
             terminal: false,
+            multiplexer: None,
+            priority: 0,
+            fallthrough: false,
+            mimes: Vec::new(),
+            schemes: Vec::new(),
+            kind: None,
+            match_path: MatchPath::Normalized,
             regexes: HandlerRegexSet::new(regexes)
                 .expect("Test regex is invalid"),
+            urls: Vec::new(),
+            globs: Vec::new(),
+            when: Conditions::default(),
         };
 
         assert_eq!(regex_handler, expected_regex_handler);
@@ -171,4 +400,177 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn regex_handler_priority() -> Result<()> {
+        let handler = |name: &str, priority, fallthrough| ConfigHandler {
+            name: Some(name.to_owned()),
+            exec: format!("{} %u", name),
+            priority,
+            fallthrough,
+            regexes: vec![String::from(r"^https://example\.com/")],
+            ..Default::default()
+        };
+
+        let regex_apps = RegexApps(vec![
+            handler("low", -1, false).compile_regex()?,
+            handler("first", 0, false).compile_regex()?,
+            handler("high", 10, true).compile_regex()?,
+            handler("second", 0, false).compile_regex()?,
+        ]);
+
+        let path =
+            UserPath::Url(Url::parse("https://example.com/page").unwrap());
+
+        assert_eq!(
+            regex_apps
+                .get_handlers(&path)
+                .iter()
+                .map(|h| h.name())
+                .collect::<Vec<_>>(),
+            vec!["high", "first", "second", "low"]
+        );
+
+        // "high" falls through, so it is skipped when not selecting
+        assert_eq!(regex_apps.get_handler(&path)?.name(), "first");
+
+        Ok(())
+    }
+
+    #[test]
+    fn regex_handler_conditions() -> Result<()> {
+        let handler = |mimes: &[&str], schemes: &[&str], kind| ConfigHandler {
+            exec: String::from("mpv %u"),
+            mimes: mimes.iter().map(|m| m.to_string()).collect(),
+            schemes: schemes.iter().map(|s| s.to_string()).collect(),
+            kind,
+            regexes: vec![String::from(r"video")],
+            ..Default::default()
+        };
+
+        let url =
+            UserPath::Url(Url::parse("https://example.com/video").unwrap());
+        let file = UserPath::File("./tests/video.mp4".into());
+
+        let only_urls =
+            handler(&[], &[], Some(PathKind::Url)).compile_regex()?;
+        assert!(only_urls.is_match(&url, &url.normalized()));
+        assert!(!only_urls.is_match(&file, &file.normalized()));
+
+        let only_files =
+            handler(&[], &[], Some(PathKind::File)).compile_regex()?;
+        assert!(!only_files.is_match(&url, &url.normalized()));
+        assert!(only_files.is_match(&file, &file.normalized()));
+
+        let https = handler(&[], &["HTTPS"], None).compile_regex()?;
+        assert!(https.is_match(&url, &url.normalized()));
+        assert!(!https.is_match(&file, &file.normalized()));
+
+        let videos = handler(&["video/*"], &[], None).compile_regex()?;
+        assert!(!videos.is_match(&url, &url.normalized()));
+        assert!(videos.is_match(&file, &file.normalized()));
+
+        Ok(())
+    }
+
+    #[test]
+    fn regex_handler_match_path() -> Result<()> {
+        let cwd = std::env::current_dir()?;
+        let handler = |match_path| ConfigHandler {
+            exec: String::from("cat %f"),
+            match_path,
+            regexes: vec![format!(
+                "^{}$",
+                regex::escape(&cwd.join("tests/cat").to_string_lossy())
+            )],
+            ..Default::default()
+        };
+
+        let regex_apps =
+            RegexApps(vec![handler(MatchPath::Raw).compile_regex()?]);
+        assert!(regex_apps
+            .get_handler(&UserPath::File("./tests/cat".into()))
+            .is_err());
+
+        let regex_apps =
+            RegexApps(vec![handler(MatchPath::Normalized).compile_regex()?]);
+        assert!(regex_apps
+            .get_handler(&UserPath::File("./tests/cat".into()))
+            .is_ok());
+
+        Ok(())
+    }
+
+    #[test]
+    fn url_rule_handlers() -> Result<()> {
+        let rule = ConfigUrlRule {
+            host: Some(String::from("*.youtube.com")),
+            path: Some(String::from("/watch")),
+            query: vec![String::from("v")],
+            ..Default::default()
+        };
+
+        let regex_apps = RegexApps(vec![ConfigHandler {
+            exec: String::from("mpv %u"),
+            urls: vec![rule],
+            ..Default::default()
+        }
+        .compile_regex()?]);
+
+        let url = |s: &str| UserPath::Url(Url::parse(s).unwrap());
+
+        assert!(regex_apps
+            .get_handler(&url("https://www.youtube.com/watch?v=dQw4w9WgXcQ"))
+            .is_ok());
+        assert!(regex_apps
+            .get_handler(&url("https://www.youtube.com/feed"))
+            .is_err());
+        assert!(regex_apps
+            .get_handler(&UserPath::File("www.youtube.com/watch".into()))
+            .is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn glob_handlers() -> Result<()> {
+        let handler = |glob: &str| -> Result<RegexApps> {
+            Ok(RegexApps(vec![ConfigHandler {
+                exec: String::from("cat %f"),
+                globs: vec![glob.to_owned()],
+                ..Default::default()
+            }
+            .compile_regex()?]))
+        };
+        let file = |path: &str| UserPath::File(path.into());
+
+        let compound = handler("*.tar.gz")?;
+        assert!(compound.get_handler(&file("a/b/archive.tar.gz")).is_ok());
+        assert!(compound.get_handler(&file("archive.gz")).is_err());
+
+        let var = |name: &str| match name {
+            "HANDLR_TEST_DIR" => Some(String::from("tests")),
+            "HOME" => Some(String::from("/home/user")),
+            _ => None,
+        };
+        let glob = |glob: &str| FileGlob::with_env(glob, var);
+        let matches = |glob: &FileGlob, path: &str| {
+            glob.is_match(Path::new(&file(path).normalized()))
+        };
+
+        let directory = glob("$HANDLR_TEST_DIR/*.vim")?;
+        assert!(matches(&directory, "./tests/rust.vim"));
+        assert!(!matches(&directory, "tests/sub/rust.vim"));
+        assert!(!matches(&directory, "rust.vim"));
+
+        let recursive = glob("~/**/*.pdf")?;
+        assert!(matches(&recursive, "/home/user/Downloads/a/b.pdf"));
+        assert!(!matches(&recursive, "/home/other/b.pdf"));
+
+        // unset variables are kept instead of failing the whole handler
+        let unset = glob("$HANDLR_UNSET/*.vim")?;
+        assert!(!matches(&unset, "./tests/rust.vim"));
+
+        Ok(())
+    }
 }
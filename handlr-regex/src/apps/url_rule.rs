@@ -0,0 +1,121 @@
+use crate::error::Result;
+use glob::Pattern;
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+// used for deserializing from config file
+// every given part has to match for the rule to match
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ConfigUrlRule {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scheme: Option<String>,
+    // `*.example.com` matches example.com and all of its subdomains
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub host: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub port: Option<u16>,
+    // glob matched against the path of the URL
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    // names of query parameters that have to be present
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub query: Vec<String>,
+}
+
+impl ConfigUrlRule {
+    // convert to UrlRule
+    pub(super) fn compile(&self) -> Result<UrlRule> {
+        Ok(UrlRule {
+            scheme: self.scheme.as_ref().map(|s| s.to_lowercase()),
+            host: self.host.as_ref().map(|h| h.to_lowercase()),
+            port: self.port,
+            path: self.path.as_deref().map(Pattern::new).transpose()?,
+            query: self.query.clone(),
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct UrlRule {
+    scheme: Option<String>,
+    host: Option<String>,
+    port: Option<u16>,
+    path: Option<Pattern>,
+    query: Vec<String>,
+}
+
+impl UrlRule {
+    pub fn is_match(&self, url: &Url) -> bool {
+        self.scheme
+            .as_ref()
+            .is_none_or(|scheme| scheme == url.scheme())
+            && self.host.as_ref().is_none_or(|host| {
+                url.host_str().is_some_and(|h| host_matches(host, h))
+            })
+            && self
+                .port
+                .is_none_or(|port| url.port_or_known_default() == Some(port))
+            && self
+                .path
+                .as_ref()
+                .is_none_or(|path| path.matches(url.path()))
+            && self.query.iter().all(|param| {
+                url.query_pairs().any(|(name, _)| name == param.as_str())
+            })
+    }
+}
+
+fn host_matches(pattern: &str, host: &str) -> bool {
+    let host = host.to_lowercase();
+    match pattern.strip_prefix("*.") {
+        Some(domain) => {
+            host == domain
+                || host
+                    .strip_suffix(domain)
+                    .is_some_and(|sub| sub.ends_with('.'))
+        }
+        None => host == pattern,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn url_rules() -> Result<()> {
+        let rule = ConfigUrlRule {
+            scheme: Some(String::from("HTTPS")),
+            host: Some(String::from("*.youtube.com")),
+            port: None,
+            path: Some(String::from("/watch*")),
+            query: vec![String::from("v")],
+        }
+        .compile()?;
+
+        let url = |s: &str| Url::parse(s).unwrap();
+
+        assert!(rule.is_match(&url("https://youtube.com/watch?v=dQw4w9WgXcQ")));
+        assert!(rule.is_match(&url(
+            "https://www.YouTube.com/watch?list=abc&v=dQw4w9WgXcQ"
+        )));
+        assert!(!rule.is_match(&url("http://youtube.com/watch?v=dQw4w9WgXcQ")));
+        assert!(!rule.is_match(&url("https://notyoutube.com/watch?v=abc")));
+        assert!(!rule.is_match(&url("https://youtube.com/watch")));
+        assert!(!rule.is_match(&url("https://youtube.com/feed?v=abc")));
+
+        let rule = ConfigUrlRule {
+            host: Some(String::from("localhost")),
+            port: Some(8080),
+            ..Default::default()
+        }
+        .compile()?;
+
+        assert!(rule.is_match(&url("http://localhost:8080/")));
+        assert!(!rule.is_match(&url("http://localhost/")));
+        assert!(!rule.is_match(&url("http://sub.localhost:8080/")));
+
+        Ok(())
+    }
+}
@@ -1,7 +1,8 @@
 use crate::{
-    apps::{RegexApps, RegexHandler, SystemApps},
-    common::Handler,
-    Error, ErrorKind, GenericHandler, Result, UserPath, CONFIG,
+    apps::{env_handler, CommandApps, RegexApps, SystemApps},
+    common::{ExecMode, Handler},
+    selector::{self, SelectorEntry, SelectorTarget},
+    utils, Error, ErrorKind, GenericHandler, Result, UserPath, CONFIG,
 };
 use mime::Mime;
 use once_cell::sync::Lazy;
@@ -22,6 +23,10 @@ pub struct MimeApps {
     default_apps: HashMap<Mime, VecDeque<Handler>>,
     system_apps: SystemApps,
     regex_apps: RegexApps,
+    command_apps: CommandApps,
+    tty_apps: CommandApps,
+    // whether to use the selector, overriding the config for this run
+    select: Option<bool>,
 }
 
 impl MimeApps {
@@ -36,6 +41,18 @@ impl MimeApps {
         self.default_apps.insert(mime, vec![handler].into());
     }
 
+    // Use the selector or not for every mime, instead of following the config
+    pub fn set_select(&mut self, select: Option<bool>) {
+        self.select = select;
+    }
+
+    fn selector_enabled(&self, mime: Option<&Mime>) -> bool {
+        self.select.unwrap_or_else(|| match mime {
+            Some(mime) => CONFIG.selector_enabled(mime),
+            None => CONFIG.enable_selector,
+        })
+    }
+
     pub fn remove_handler(&mut self, mime: &Mime) -> Result<()> {
         if let Some(_removed) = self.default_apps.remove(mime) {
             self.save()?;
@@ -44,62 +61,233 @@ impl MimeApps {
         Ok(())
     }
 
-    pub fn get_handler(&self, mime: &Mime) -> Result<Handler> {
-        match self.get_handler_from_user(mime) {
+    pub fn get_handler(&self, mime: &Mime) -> Result<GenericHandler> {
+        if utils::is_graphical() {
+            return self.get_handler_with(mime, false);
+        }
+
+        // without a graphical session, GUI programs are only used
+        // when nothing can run in the terminal
+        match self
+            .get_tty_handler(mime)
+            .or_else(|_| self.get_handler_with(mime, true))
+        {
+            Err(e) if matches!(*e.kind, ErrorKind::Cancelled) => Err(e),
+            h => h.or_else(|_| self.get_handler_with(mime, false)),
+        }
+    }
+
+    // get the handler for a mime, optionally only considering handlers
+    // that run in a terminal
+    fn get_handler_with(
+        &self,
+        mime: &Mime,
+        terminal_only: bool,
+    ) -> Result<GenericHandler> {
+        match self.get_handler_from_user(mime, terminal_only) {
             Err(e) if matches!(*e.kind, ErrorKind::Cancelled) => Err(e),
             h => h
                 .or_else(|_| {
                     let wildcard =
                         Mime::from_str(&format!("{}/*", mime.type_())).unwrap();
-                    self.get_handler_from_user(&wildcard)
+                    self.get_handler_from_user(&wildcard, terminal_only)
                 })
-                .or_else(|_| self.get_handler_from_added_associations(mime)),
+                .or_else(|_| {
+                    self.get_handler_from_added_associations(
+                        mime,
+                        terminal_only,
+                    )
+                    .map(GenericHandler::Handler)
+                })
+                .or_else(|_| self.get_env_handler(mime, terminal_only)),
         }
     }
 
-    fn get_handler_from_user(&self, mime: &Mime) -> Result<Handler> {
-        match self.default_apps.get(mime) {
-            Some(handlers) if CONFIG.enable_selector && handlers.len() > 1 => {
-                let handlers = handlers
-                    .iter()
-                    .map(|h| (h, h.get_entry().unwrap().name))
-                    .collect::<Vec<_>>();
-
-                let handler = {
-                    let name =
-                        CONFIG.select(handlers.iter().map(|h| h.1.clone()))?;
-
-                    handlers
-                        .into_iter()
-                        .find(|h| h.1 == name)
-                        .unwrap()
-                        .0
-                        .clone()
-                };
-
-                Ok(handler)
+    // handler from $BROWSER, $VISUAL or $EDITOR, if enabled in the config
+    fn get_env_handler(
+        &self,
+        mime: &Mime,
+        terminal_only: bool,
+    ) -> Result<GenericHandler> {
+        CONFIG
+            .env_fallbacks
+            .then(|| env_handler(mime))
+            .flatten()
+            .map(GenericHandler::CommandHandler)
+            .filter(|h| !terminal_only || is_terminal(h))
+            .ok_or_else(|| Error::from(ErrorKind::NotFound(mime.to_string())))
+    }
+
+    // handler from tty_handlers in the config for this mime or its wildcard
+    fn get_tty_handler(&self, mime: &Mime) -> Result<GenericHandler> {
+        let wildcard = Mime::from_str(&format!("{}/*", mime.type_())).unwrap();
+
+        self.tty_apps
+            .get_handlers(mime)
+            .into_iter()
+            .chain(self.tty_apps.get_handlers(&wildcard))
+            .next()
+            .map(GenericHandler::CommandHandler)
+            .ok_or_else(|| Error::from(ErrorKind::NotFound(mime.to_string())))
+    }
+
+    // handlers set by the user for exactly this mime
+    // handlers from mimeapps.list come before those from the config file
+    fn get_user_handlers(
+        &self,
+        mime: &Mime,
+        terminal_only: bool,
+    ) -> Vec<GenericHandler> {
+        self.default_apps
+            .get(mime)
+            .into_iter()
+            .flatten()
+            .cloned()
+            .map(GenericHandler::Handler)
+            .chain(
+                self.command_apps
+                    .get_handlers(mime)
+                    .into_iter()
+                    .map(GenericHandler::CommandHandler),
+            )
+            .filter(|h| !terminal_only || is_terminal(h))
+            .collect()
+    }
+
+    fn get_handler_from_user(
+        &self,
+        mime: &Mime,
+        terminal_only: bool,
+    ) -> Result<GenericHandler> {
+        let handlers = self.get_user_handlers(mime, terminal_only);
+
+        match handlers.len() {
+            0 => Err(Error::from(ErrorKind::NotFound(mime.to_string()))),
+            n if self.selector_enabled(Some(mime)) && n > 1 => {
+                Self::select_handler(
+                    handlers,
+                    &SelectorTarget {
+                        mime: Some(mime.to_string()),
+                        ..Default::default()
+                    },
+                )
             }
-            Some(handlers) => Ok(handlers.get(0).unwrap().clone()),
-            None => Err(Error::from(ErrorKind::NotFound(mime.to_string()))),
+            _ => Ok(handlers.into_iter().next().unwrap()),
+        }
+    }
+
+    // get the handler for a mime without prompting the user
+    fn get_default_handler(&self, mime: &Mime) -> Result<GenericHandler> {
+        let wildcard = Mime::from_str(&format!("{}/*", mime.type_())).unwrap();
+
+        self.get_user_handlers(mime, false)
+            .into_iter()
+            .next()
+            .or_else(|| {
+                self.get_user_handlers(&wildcard, false).into_iter().next()
+            })
+            .ok_or_else(|| Error::from(ErrorKind::NotFound(mime.to_string())))
+            .or_else(|_| {
+                self.get_handler_from_added_associations(mime, false)
+                    .map(GenericHandler::Handler)
+            })
+    }
+
+    // prompt the user to pick one of several handlers using the selector
+    fn select_handler(
+        handlers: Vec<GenericHandler>,
+        target: &SelectorTarget,
+    ) -> Result<GenericHandler> {
+        if handlers.len() == 1 {
+            return Ok(handlers.into_iter().next().unwrap());
         }
+
+        let names = handlers
+            .iter()
+            .map(|h| h.name())
+            .collect::<Result<Vec<_>>>()?;
+
+        let entries = handlers
+            .iter()
+            .zip(names.iter())
+            .map(|(handler, name)| SelectorEntry {
+                // handlers sharing a name are told apart by their desktop file or command
+                label: if names.iter().filter(|n| *n == name).count() > 1 {
+                    format!("{} ({})", name, handler)
+                } else {
+                    name.clone()
+                },
+                icon: handler.get_entry().ok().and_then(|e| e.icon),
+                id: Some(handler.to_string()),
+            })
+            .collect::<Vec<_>>();
+
+        let index = selector::select(&entries, target)?;
+        Ok(handlers.into_iter().nth(index).unwrap())
     }
 
     fn get_handler_from_added_associations(
         &self,
         mime: &Mime,
+        terminal_only: bool,
     ) -> Result<Handler> {
         self.added_associations
             .get(mime)
-            .map(|h| h.get(0).unwrap().clone())
-            .or_else(|| self.system_apps.get_handler(mime))
+            .into_iter()
+            .flatten()
+            .cloned()
+            .chain(self.system_apps.get_handlers(mime).into_iter().flatten())
+            .find(|h| {
+                !terminal_only
+                    || is_terminal(&GenericHandler::Handler(h.clone()))
+            })
             .ok_or_else(|| Error::from(ErrorKind::NotFound(mime.to_string())))
     }
 
-    fn get_handler_from_regex_handlers(
-        &self,
-        path: &UserPath,
-    ) -> Result<RegexHandler> {
-        self.regex_apps.get_handler(path)
+    // get the handler for a path, taking regex handlers into account
+    fn get_handler_from_path(&self, path: &UserPath) -> Result<GenericHandler> {
+        let mut regex_handlers = self.regex_apps.get_handlers(path);
+
+        // without a graphical session, terminal handlers are preferred
+        // but GUI handlers are kept when no terminal handler matches
+        if !utils::is_graphical()
+            && regex_handlers.iter().any(|h| h.get_entry().terminal)
+        {
+            regex_handlers.retain(|h| h.get_entry().terminal);
+        }
+
+        // handlers that fall through are only used through the selector,
+        // or when there is nothing else to open the path with
+        let mime = path.get_mime().ok();
+        if self.selector_enabled(mime.as_ref())
+            && (regex_handlers.len() > 1
+                || regex_handlers.iter().any(|h| h.falls_through()))
+        {
+            let mut handlers = regex_handlers
+                .into_iter()
+                .map(GenericHandler::RegexHandler)
+                .collect::<Vec<_>>();
+
+            if let Some(handler) = mime
+                .as_ref()
+                .and_then(|mime| self.get_default_handler(mime).ok())
+            {
+                handlers.push(handler);
+            }
+
+            return Self::select_handler(
+                handlers,
+                &SelectorTarget {
+                    path: Some(path.to_string()),
+                    mime: mime.map(|mime| mime.to_string()),
+                },
+            );
+        }
+
+        match regex_handlers.into_iter().find(|h| !h.falls_through()) {
+            Some(handler) => Ok(GenericHandler::RegexHandler(handler)),
+            None => self.get_handler(&path.get_mime()?),
+        }
     }
 
     pub fn show_handler(&self, mime: &Mime, output_json: bool) -> Result<()> {
@@ -145,6 +333,9 @@ impl MimeApps {
             default_apps: HashMap::default(),
             system_apps: SystemApps::populate()?,
             regex_apps: RegexApps::populate(),
+            command_apps: CommandApps::populate(),
+            tty_apps: CommandApps::populate_tty(),
+            select: None,
         };
 
         file.into_inner().for_each(|line| {
@@ -233,11 +424,24 @@ impl MimeApps {
                 .collect::<Vec<_>>()
         };
 
+        // default apps along with handlers from the config file
+        let default_rows = {
+            let mut map = self.command_apps.names();
+            for (mime, handlers) in self.default_apps.iter() {
+                let names = map.entry(mime.clone()).or_default();
+                names.splice(0..0, handlers.iter().map(|h| h.to_string()));
+            }
+            map.into_iter()
+                .sorted()
+                .map(|(k, v)| vec![k.to_string(), v.join(", ")])
+                .collect::<Vec<_>>()
+        };
+
         let table = ascii_table::AsciiTable::default();
 
         if detailed {
             println!("Default Apps");
-            table.print(to_rows(&self.default_apps));
+            table.print(default_rows);
             if !self.added_associations.is_empty() {
                 println!("Added Associations");
                 table.print(to_rows(&self.added_associations));
@@ -245,7 +449,7 @@ impl MimeApps {
             println!("System Apps");
             table.print(to_rows(&self.system_apps.0));
         } else {
-            table.print(to_rows(&self.default_apps));
+            table.print(default_rows);
         }
 
         Ok(())
@@ -265,37 +469,257 @@ impl MimeApps {
 
         Ok(())
     }
-    pub fn open_paths(&self, paths: &[UserPath]) -> Result<()> {
-        let mut handlers: HashMap<GenericHandler, Vec<String>> = HashMap::new();
+    // Open the paths with their handlers, in the order they were given
+    // Paths that cannot be opened are reported without stopping the others
+    pub fn open_paths(
+        &mut self,
+        paths: &[UserPath],
+        with_menu: bool,
+    ) -> Result<()> {
+        let mut handlers = Vec::new();
+        let mut failed = Vec::new();
+        // handlers picked from the menu, by the handlers offered
+        let mut picked: Vec<(Vec<GenericHandler>, GenericHandler)> = Vec::new();
 
         for path in paths.iter() {
-            handlers
-                .entry(
-                    if let Ok(handler) =
-                        self.get_handler_from_regex_handlers(path)
-                    {
-                        GenericHandler::RegexHandler(handler)
-                    } else {
-                        GenericHandler::Handler(
-                            self.get_handler(&path.get_mime()?)?,
-                        )
-                    },
-                )
-                .or_default()
-                .push(path.to_string())
+            let handler = if with_menu {
+                let capable = self.get_capable_handlers(path);
+                match picked.iter().find(|(offered, _)| *offered == capable) {
+                    Some((_, handler)) => Ok(handler.clone()),
+                    None => self.pick_handler(path, capable.clone()).inspect(
+                        |handler| picked.push((capable, handler.clone())),
+                    ),
+                }
+            } else {
+                self.get_handler_from_path(path)
+            };
+
+            match handler {
+                Ok(handler) => match path.argument() {
+                    Ok(arg) => handlers.push((handler, arg)),
+                    Err(e) => failed.push((vec![path.to_string()], e)),
+                },
+                Err(e) if matches!(*e.kind, ErrorKind::Cancelled) => {
+                    return Err(e)
+                }
+                Err(e) => failed.push((vec![path.to_string()], e)),
+            }
         }
 
-        for (handler, paths) in handlers.into_iter() {
-            handler.open(paths)?;
+        let report = |failed: &[(Vec<String>, Error)]| {
+            failed.iter().try_for_each(|(paths, e)| {
+                utils::report(&format!("{}: {}", paths.join(", "), e))
+            })
+        };
+
+        // paths that could not be resolved are reported even if
+        // opening the others is cancelled
+        report(&failed)?;
+
+        let groups = group_in_order(handlers);
+        confirm_processes(&groups, CONFIG.confirm_processes, utils::ask)?;
+        let not_opened = open_groups(groups);
+        report(&not_opened)?;
+        failed.extend(not_opened);
+
+        match failed.iter().map(|(paths, _)| paths.len()).sum() {
+            0 => Ok(()),
+            n => Err(Error::from(ErrorKind::OpenFailed(n))),
         }
+    }
 
-        Ok(())
+    // every handler that can open a path, for the "open with" menu:
+    // matching regex handlers, then handlers set by the user, added associations
+    // and every application declaring support for its mime
+    fn get_capable_handlers(&self, path: &UserPath) -> Vec<GenericHandler> {
+        use itertools::Itertools;
+
+        let mut handlers = self
+            .regex_apps
+            .get_handlers(path)
+            .into_iter()
+            .map(GenericHandler::RegexHandler)
+            .collect::<Vec<_>>();
+
+        if let Ok(mime) = path.get_mime() {
+            let wildcard =
+                Mime::from_str(&format!("{}/*", mime.type_())).unwrap();
+
+            handlers.extend(self.get_user_handlers(&mime, false));
+            handlers.extend(self.get_user_handlers(&wildcard, false));
+            handlers.extend(
+                self.added_associations
+                    .get(&mime)
+                    .into_iter()
+                    .flatten()
+                    .cloned()
+                    .chain(
+                        self.system_apps
+                            .get_handlers(&mime)
+                            .into_iter()
+                            .flatten(),
+                    )
+                    .map(GenericHandler::Handler),
+            );
+        }
+
+        handlers.into_iter().unique().collect()
+    }
+
+    // prompt the user to pick one of the capable handlers for a path
+    // and offer to make a desktop entry the default for its mime
+    fn pick_handler(
+        &mut self,
+        path: &UserPath,
+        handlers: Vec<GenericHandler>,
+    ) -> Result<GenericHandler> {
+        let mime = path.get_mime()?;
+
+        if handlers.is_empty() {
+            return Err(Error::from(ErrorKind::NotFound(mime.to_string())));
+        }
+
+        let target = SelectorTarget {
+            path: Some(path.to_string()),
+            mime: Some(mime.to_string()),
+        };
+        let handler = Self::select_handler(handlers, &target)?;
+
+        if let GenericHandler::Handler(desktop) = &handler {
+            let is_default = self
+                .default_apps
+                .get(&mime)
+                .is_some_and(|h| h.len() == 1 && h.front() == Some(desktop));
+
+            if !is_default {
+                let always =
+                    format!("Always use {} for {}", handler.name()?, mime);
+                let choices = [
+                    SelectorEntry::new("Just once"),
+                    SelectorEntry::new(always),
+                ];
+                // cancelling only skips making it the default
+                if selector::select(&choices, &target).is_ok_and(|i| i == 1) {
+                    self.set_handler(mime, desktop.clone());
+                    self.save()?;
+                }
+            }
+        }
+
+        Ok(handler)
+    }
+}
+
+// Group items by their key, keeping the order keys and items first appear in
+fn group_in_order<K: PartialEq, T>(items: Vec<(K, T)>) -> Vec<(K, Vec<T>)> {
+    let mut groups: Vec<(K, Vec<T>)> = Vec::new();
+    for (key, item) in items {
+        match groups.iter_mut().find(|(k, _)| *k == key) {
+            Some((_, group)) => group.push(item),
+            None => groups.push((key, vec![item])),
+        }
+    }
+    groups
+}
+
+// Ask before opening the groups when they would start too many processes
+// When there is no one to ask, they are opened after saying so on stderr
+fn confirm_processes(
+    groups: &[(GenericHandler, Vec<String>)],
+    max: Option<usize>,
+    ask: impl FnOnce(&str) -> Option<bool>,
+) -> Result<()> {
+    let max = match max {
+        Some(max) if max > 0 => max,
+        _ => return Ok(()),
+    };
+
+    let processes = groups
+        .iter()
+        .map(|(handler, paths)| {
+            handler.get_entry().map_or(1, |entry| {
+                entry.invocations(ExecMode::Open, paths.clone()).len()
+            })
+        })
+        .sum::<usize>();
+    if processes <= max {
+        return Ok(());
+    }
+
+    let question = format!(
+        "Start {} processes to open {} paths?",
+        processes,
+        groups.iter().map(|(_, paths)| paths.len()).sum::<usize>()
+    );
+
+    match ask(&question) {
+        Some(true) => Ok(()),
+        Some(false) => Err(Error::from(ErrorKind::Cancelled)),
+        None => {
+            eprintln!("handlr: {} Starting them without asking.", question);
+            Ok(())
+        }
     }
 }
 
+// Open each group of paths with its handler
+// Handlers running in the current terminal take turns using it,
+// while the others are started at the same time
+// Returns the paths of the groups that could not be opened
+fn open_groups(
+    groups: Vec<(GenericHandler, Vec<String>)>,
+) -> Vec<(Vec<String>, Error)> {
+    let in_terminal = |handler: &GenericHandler| {
+        is_terminal(handler) && utils::use_current_terminal()
+    };
+
+    let results = std::thread::scope(|scope| {
+        let threads = groups
+            .iter()
+            .map(|(handler, paths)| {
+                (!in_terminal(handler))
+                    .then(|| scope.spawn(move || handler.open(paths.clone())))
+            })
+            .collect::<Vec<_>>();
+
+        // handlers in the terminal run while the others are still starting,
+        // which may take a while with process_limit
+        let in_place = groups
+            .iter()
+            .zip(threads.iter())
+            .map(|((handler, paths), thread)| {
+                thread.is_none().then(|| handler.open(paths.clone()))
+            })
+            .collect::<Vec<_>>();
+
+        threads
+            .into_iter()
+            .zip(in_place)
+            .map(|(thread, result)| match thread {
+                Some(thread) => thread
+                    .join()
+                    .unwrap_or_else(|e| std::panic::resume_unwind(e)),
+                None => result.unwrap(),
+            })
+            .collect::<Vec<_>>()
+    });
+
+    groups
+        .into_iter()
+        .zip(results)
+        .filter_map(|((_, paths), result)| Some((paths, result.err()?)))
+        .collect()
+}
+
+// whether a handler runs in a terminal rather than a graphical session
+fn is_terminal(handler: &GenericHandler) -> bool {
+    handler.get_entry().is_ok_and(|entry| entry.terminal)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::apps::ConfigHandler;
 
     #[test]
     fn wildcard_mimes() -> Result<()> {
@@ -331,4 +755,147 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn tty_handlers() -> Result<()> {
+        let mut user_apps = MimeApps {
+            tty_apps: CommandApps::from_tty_handlers(
+                &[("text/html", "w3m"), ("image/*", "chafa")]
+                    .iter()
+                    .map(|(m, e)| (m.to_string(), e.to_string()))
+                    .collect(),
+            ),
+            ..Default::default()
+        };
+        user_apps.add_handler(
+            Mime::from_str("text/html")?,
+            Handler::assume_valid("firefox.desktop".into()),
+        );
+
+        let tty_handler = |mime: &str| -> Result<String> {
+            Ok(user_apps
+                .get_tty_handler(&Mime::from_str(mime)?)?
+                .to_string())
+        };
+        assert_eq!(tty_handler("text/html")?, "w3m");
+        assert_eq!(tty_handler("image/png")?, "chafa");
+        assert!(tty_handler("video/mp4").is_err());
+
+        // GUI handlers are skipped when only terminal handlers are wanted
+        let html = Mime::from_str("text/html")?;
+        assert!(user_apps.get_handler_with(&html, true).is_err());
+        assert_eq!(
+            user_apps.get_handler_with(&html, false)?.to_string(),
+            "firefox.desktop"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn capable_handlers() -> Result<()> {
+        let https = Mime::from_str("x-scheme-handler/https")?;
+        let handler = |name: &str| Handler::assume_valid(name.into());
+
+        let mut user_apps = MimeApps {
+            system_apps: SystemApps(
+                [(
+                    https.clone(),
+                    vec![
+                        handler("chromium.desktop"),
+                        handler("firefox.desktop"),
+                    ]
+                    .into(),
+                )]
+                .iter()
+                .cloned()
+                .collect(),
+            ),
+            ..Default::default()
+        };
+        user_apps.add_handler(https.clone(), handler("firefox.desktop"));
+        user_apps
+            .added_associations
+            .insert(https, vec![handler("brave.desktop")].into());
+
+        let names = user_apps
+            .get_capable_handlers(&UserPath::from_str("https://example.com")?)
+            .into_iter()
+            .map(|h| h.to_string())
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            names,
+            vec!["firefox.desktop", "brave.desktop", "chromium.desktop"]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn groups_keep_order() {
+        let groups = group_in_order(vec![
+            ("mpv", "b.mp4"),
+            ("imv", "a.png"),
+            ("mpv", "a.mp4"),
+            ("zathura", "a.pdf"),
+            ("imv", "c.png"),
+        ]);
+
+        assert_eq!(
+            groups,
+            vec![
+                ("mpv", vec!["b.mp4", "a.mp4"]),
+                ("imv", vec!["a.png", "c.png"]),
+                ("zathura", vec!["a.pdf"]),
+            ]
+        );
+    }
+
+    #[test]
+    fn process_confirmation() {
+        let groups = ["a.png", "b.png", "c.png"]
+            .iter()
+            .map(|path| {
+                let handler =
+                    Handler::assume_valid(format!("{}.desktop", path).into());
+                (GenericHandler::Handler(handler), vec![path.to_string()])
+            })
+            .collect::<Vec<_>>();
+        let never = |_: &str| -> Option<bool> { unreachable!() };
+
+        assert!(confirm_processes(&groups, None, never).is_ok());
+        assert!(confirm_processes(&groups, Some(0), never).is_ok());
+        assert!(confirm_processes(&groups, Some(3), never).is_ok());
+
+        assert!(confirm_processes(&groups, Some(2), |_| Some(true)).is_ok());
+        assert!(confirm_processes(&groups, Some(2), |_| Some(false)).is_err());
+        // like with --stdin outside of a terminal
+        assert!(confirm_processes(&groups, Some(2), |_| None).is_ok());
+    }
+
+    #[test]
+    fn fallthrough_handlers() -> Result<()> {
+        let handler: ConfigHandler = toml::from_str(
+            r#"
+            exec = "freetube %u"
+            fallthrough = true
+            regexes = ['^https://youtube\.com/']
+            "#,
+        )?;
+        let user_apps = MimeApps {
+            regex_apps: RegexApps(vec![handler.compile_regex()?]),
+            select: Some(true),
+            ..Default::default()
+        };
+        let path = UserPath::from_str("https://youtube.com/x")?;
+
+        // without a default handler to pick from, it is used without asking
+        assert_eq!(
+            user_apps.get_handler_from_path(&path)?.to_string(),
+            "freetube %u"
+        );
+
+        Ok(())
+    }
 }
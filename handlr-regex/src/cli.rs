@@ -1,5 +1,6 @@
 use crate::common::{Handler, MimeOrExtension, UserPath};
-use clap::Parser;
+use clap::{Args, Parser, Subcommand};
+use std::path::PathBuf;
 
 /// A better xdg-utils
 ///
@@ -13,6 +14,21 @@ use clap::Parser;
 #[clap(global_setting = clap::AppSettings::DeriveDisplayOrder)]
 #[clap(disable_help_subcommand = true)]
 #[clap(version, about)]
+pub struct Cli {
+    #[clap(long, global = true)]
+    /// Use this config file instead of ~/.config/handlr/handlr.toml
+    ///
+    /// Site-wide defaults from handlr/handlr.toml in $XDG_CONFIG_DIRS (i.e. /etc/xdg)
+    /// and HANDLR_* environment variables still apply.
+    pub config: Option<PathBuf>,
+    #[clap(subcommand)]
+    /// Subcommand to run
+    pub command: Cmd,
+}
+
+/// Subcommands of handlr
+#[deny(missing_docs)]
+#[derive(Subcommand)]
 pub enum Cmd {
     /// List default apps and the associated handlers
     ///
@@ -29,13 +45,29 @@ pub enum Cmd {
     /// Open a path/URL with its default handler
     ///
     /// Unlike xdg-open and similar resource openers, multiple paths/URLs may be supplied.
+    /// They are passed to their handlers in the given order, and handlers are started at the same time,
+    /// except for those running in the current terminal, which take turns.
+    /// Paths that cannot be opened are reported without stopping the others.
     ///
-    /// If multiple handlers are set and `enable_selector` is set to true,
+    /// If multiple handlers are set and the selector is enabled for the mimetype
+    /// with `enable_selector`, `selector_mimes` or `--select`,
     /// you will be prompted to select one using `selector` from ~/.config/handlr/handlr.toml.
     /// Otherwise, the default handler will be opened.
+    ///
+    /// With `--with-menu` or `open_with_menu` set to true, you will be prompted to select
+    /// any application that can open the path instead, and whether to always use it.
     Open {
-        #[clap(required = true)]
-        /// Paths/URLs to open
+        #[clap(long)]
+        /// Select from every application that can open the paths/URLs
+        with_menu: bool,
+        #[clap(flatten)]
+        /// Whether to use the selector
+        selector: SelectorArgs,
+        #[clap(flatten)]
+        /// Whether to read paths/URLs from stdin
+        stdin: StdinArgs,
+        #[clap(required_unless_present = "stdin")]
+        /// Paths/URLs to open, or - to open the content of stdin
         paths: Vec<UserPath>,
     },
 
@@ -71,10 +103,14 @@ pub enum Cmd {
     ///
     /// Only supports wildcards for mimetypes for handlers that have been set or added with wildcards.
     ///
-    /// If multiple handlers are set and `enable_selector` is set to true,
+    /// If multiple handlers are set and the selector is enabled for the mimetype
+    /// with `enable_selector`, `selector_mimes` or `--select`,
     /// you will be prompted to select one using `selector` from ~/.config/handlr/handlr.toml.
     /// Otherwise, the default handler will be opened.
     Launch {
+        #[clap(flatten)]
+        /// Whether to use the selector
+        selector: SelectorArgs,
         /// Mimetype or file extension to launch the handler of
         mime: MimeOrExtension,
         /// Arguments to pass to handler program
@@ -83,7 +119,8 @@ pub enum Cmd {
 
     /// Get handler for this mime/extension
     ///
-    /// If multiple handlers are set and `enable_selector` is set to true,
+    /// If multiple handlers are set and the selector is enabled for the mimetype
+    /// with `enable_selector`, `selector_mimes` or `--select`,
     /// you will be prompted to select one using `selector` from ~/.config/handlr/handlr.toml.
     /// Otherwise, only the default handler will be printed.
     ///
@@ -111,6 +148,9 @@ pub enum Cmd {
         #[clap(long)]
         /// Output handler info as json
         json: bool,
+        #[clap(flatten)]
+        /// Whether to use the selector
+        selector: SelectorArgs,
         /// Mimetype to get the handler of
         mime: MimeOrExtension,
     },
@@ -163,10 +203,54 @@ pub enum Cmd {
         #[clap(long)]
         /// Output mimetype info as json
         json: bool,
-        /// File paths/URLs to get the mimetype of
+        #[clap(flatten)]
+        /// Whether to read paths/URLs from stdin
+        stdin: StdinArgs,
+        /// File paths/URLs to get the mimetype of, or - for the content of stdin
         paths: Vec<UserPath>,
     },
 
+    /// Inspect and change handlr's configuration
+    ///
+    /// The effective configuration is made of several layers, from least to most important:
+    ///
+    /// 1. handlr/handlr.toml in each of $XDG_CONFIG_DIRS (i.e. /etc/xdg/handlr/handlr.toml)
+    ///
+    /// 2. ~/.config/handlr/handlr.toml or the file given with `--config`
+    ///
+    /// 3. Trusted .handlr.toml files in the current directory and its ancestors, farthest first.
+    ///    Only their `handlers` and `mime_handlers` are used.
+    ///    These are only used by `open`, `get` and `launch`.
+    ///
+    /// 4. HANDLR_* environment variables, i.e. HANDLR_ENABLE_SELECTOR=true
+    ///
+    /// `handlers` and `mime_handlers` are concatenated, with the items of more important layers first.
+    /// Other values are overridden by more important layers.
+    Config {
+        #[clap(subcommand)]
+        /// Config subcommand to run
+        command: ConfigCmd,
+    },
+
+    /// Inspect and choose the terminal emulator for terminal programs
+    ///
+    /// If `x-scheme-handler/terminal` has no handler, handlr uses the first of:
+    ///
+    /// 1. $TERMINAL, if `env_fallbacks` is set to true
+    ///
+    /// 2. the first installed terminal in xdg-terminals.list
+    ///
+    /// 3. xdg-terminal-exec, if installed
+    ///
+    /// 4. the best installed terminal emulator, see `handlr terminal detect`
+    ///
+    /// None of these are saved unless `handlr terminal set` is used.
+    Terminal {
+        #[clap(subcommand)]
+        /// Terminal subcommand to run
+        command: TerminalCmd,
+    },
+
     #[clap(hide = true)]
     /// Helper subcommand for autocompletion scripts; should be hidden
     ///
@@ -183,3 +267,171 @@ pub enum Cmd {
         mimes: bool,
     },
 }
+
+/// Subcommands of handlr config
+#[deny(missing_docs)]
+#[derive(Subcommand)]
+pub enum ConfigCmd {
+    /// Print the path of the user's config file
+    ///
+    /// When using `--json`, the paths of site-wide config files are included as well.
+    Path {
+        #[clap(long)]
+        /// Output paths as json
+        json: bool,
+    },
+
+    /// Show the effective configuration with all layers merged
+    Show {
+        #[clap(long)]
+        /// Output configuration as json
+        json: bool,
+    },
+
+    /// Get the effective value of a config key
+    ///
+    /// Nested keys are separated by dots, i.e. `handlers.0.exec`.
+    Get {
+        #[clap(long)]
+        /// Output value as json
+        json: bool,
+        /// Key to get the value of
+        key: String,
+    },
+
+    /// Set a config key in the user's config file
+    ///
+    /// Nested keys are separated by dots, i.e. `handlers.0.exec`.
+    ///
+    /// The value is parsed as TOML if possible and used as a string otherwise.
+    /// The config file is only changed if the result is valid.
+    Set {
+        /// Key to set
+        key: String,
+        /// New value of the key
+        value: String,
+    },
+
+    /// Edit the user's config file with $VISUAL or $EDITOR
+    ///
+    /// Changes are made to a copy of the config file that only replaces it if it is valid
+    /// and the editor exits successfully.
+    /// Otherwise, running this again continues editing the copy.
+    Edit,
+
+    /// Trust the handlers of a per-directory config file
+    ///
+    /// `.handlr.toml` files in the current directory and its ancestors can add `handlers` and `mime_handlers`.
+    /// They are only used once trusted, either with this subcommand or by answering the prompt shown when they are found.
+    ///
+    /// Changing a trusted file makes it untrusted again.
+    Trust {
+        /// Path to the file to trust, defaults to the closest one to the current directory
+        path: Option<PathBuf>,
+    },
+
+    /// Check the configuration for errors
+    ///
+    /// Checks every layer of the configuration as well as the handlers in it.
+    ///
+    /// When using `--json`, output is in the form:
+    ///
+    /// ```json
+    ///
+    /// {
+    ///
+    ///   "valid": false,
+    ///
+    ///   "errors": ["handlers.0: regex parse error: ..."]
+    ///
+    /// }
+    ///
+    /// ```
+    Check {
+        #[clap(long)]
+        /// Output results as json
+        json: bool,
+    },
+}
+
+/// Subcommands of handlr terminal
+#[deny(missing_docs)]
+#[derive(Subcommand)]
+pub enum TerminalCmd {
+    /// Show the terminal emulator that is used and where it was found
+    ///
+    /// When using `--json`, output is in the form:
+    ///
+    /// ```json
+    ///
+    /// {
+    ///
+    ///   "terminal": "foot.desktop",
+    ///
+    ///   "source": "detected",
+    ///
+    ///   "cmd": "foot"
+    ///
+    /// }
+    ///
+    /// ```
+    Get {
+        #[clap(long)]
+        /// Output terminal info as json
+        json: bool,
+    },
+
+    /// List installed terminal emulators, best first
+    ///
+    /// Terminals in xdg-terminals.list come first, in its order,
+    /// followed by terminals handlr knows the arguments of and then the rest by desktop file name.
+    Detect {
+        #[clap(long)]
+        /// Output terminals as json
+        json: bool,
+    },
+
+    /// Set the terminal emulator as the handler of `x-scheme-handler/terminal`
+    Set {
+        /// Desktop file of the terminal emulator, defaults to the best detected one
+        terminal: Option<Handler>,
+    },
+}
+
+/// Options overriding whether to use the selector
+#[deny(missing_docs)]
+#[derive(Args)]
+pub struct SelectorArgs {
+    #[clap(long, overrides_with = "no-select")]
+    /// Select a handler if several are set, even if disabled in the config
+    select: bool,
+    #[clap(long, overrides_with = "select")]
+    /// Use the default handler, even if the selector is enabled in the config
+    no_select: bool,
+}
+
+impl SelectorArgs {
+    /// Whether to use the selector, or None to follow the config
+    pub fn enabled(&self) -> Option<bool> {
+        match (self.select, self.no_select) {
+            (true, _) => Some(true),
+            (_, true) => Some(false),
+            _ => None,
+        }
+    }
+}
+
+/// Options for reading paths/URLs from stdin
+#[deny(missing_docs)]
+#[derive(Args)]
+pub struct StdinArgs {
+    #[clap(long)]
+    /// Read paths/URLs from stdin, one per line, after those given as arguments
+    ///
+    /// Stdin is read as a list of paths/URLs here, while `-` as a path stands for the content of stdin.
+    /// The two cannot be used together.
+    pub stdin: bool,
+    #[clap(short = '0', long, requires = "stdin")]
+    /// Paths/URLs from stdin are separated by NUL characters instead of newlines, like `find -print0`
+    pub null: bool,
+}
@@ -0,0 +1,237 @@
+use crate::{common::MimeType, Error, ErrorKind, Result};
+use base64::{
+    alphabet,
+    engine::{DecodePaddingMode, GeneralPurpose, GeneralPurposeConfig},
+    Engine,
+};
+use mime::Mime;
+use sha2::{Digest, Sha256};
+use std::{
+    convert::TryFrom,
+    fs,
+    io::Read,
+    os::unix::fs::{DirBuilderExt, MetadataExt, PermissionsExt},
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::{Duration, SystemTime},
+};
+use url::Url;
+
+// How long temporary files are kept for handlers to read them
+const MAX_AGE: Duration = Duration::from_secs(24 * 60 * 60);
+
+// Data opened instead of a path, like text piped to `handlr open -`
+// Handlers get it as a temporary file in $XDG_RUNTIME_DIR/handlr
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Content {
+    // shown instead of a path
+    name: String,
+    data: Vec<u8>,
+    mime: Mime,
+}
+
+impl Content {
+    pub fn new(name: impl Into<String>, data: Vec<u8>, mime: Mime) -> Self {
+        Content {
+            name: name.into(),
+            data,
+            mime,
+        }
+    }
+
+    // Read all of stdin, guessing its mime from the data
+    pub fn from_stdin() -> Result<Self> {
+        let mut data = Vec::new();
+        std::io::stdin().lock().read_to_end(&mut data)?;
+        let mime = MimeType::try_from(data.as_slice())?.0;
+        Ok(Self::new("-", data, mime))
+    }
+
+    // Decode a data: URL, taking the mime from its media type
+    // Without one, it is plain text as in RFC 2397
+    pub fn from_data_url(url: &Url) -> Result<Self> {
+        let bad_url = || Error::from(ErrorKind::BadPath(url.to_string()));
+
+        // everything after `data:` except for the fragment
+        let rest = &url[url::Position::BeforePath..url::Position::AfterQuery];
+        let (header, data) = rest.split_once(',').ok_or_else(bad_url)?;
+        let data =
+            percent_encoding::percent_decode_str(data).collect::<Vec<_>>();
+
+        let (media_type, base64) = match header.trim_end().rsplit_once(';') {
+            Some((media_type, param))
+                if param.eq_ignore_ascii_case("base64") =>
+            {
+                (media_type, true)
+            }
+            _ => (header, false),
+        };
+
+        let data = if base64 {
+            // whitespace is allowed in the data, and padding may be left out
+            let engine = GeneralPurpose::new(
+                &alphabet::STANDARD,
+                GeneralPurposeConfig::new()
+                    .with_decode_padding_mode(DecodePaddingMode::Indifferent),
+            );
+            let data = data
+                .into_iter()
+                .filter(|b| !b.is_ascii_whitespace())
+                .collect::<Vec<_>>();
+            engine.decode(data).map_err(|_| bad_url())?
+        } else {
+            data
+        };
+
+        // parameters like charset would keep the mime from matching handlers
+        let mime = Mime::from_str(media_type.trim())
+            .ok()
+            .filter(|mime| !mime.subtype().as_str().is_empty())
+            .and_then(|mime| mime.essence_str().parse().ok())
+            .unwrap_or(mime::TEXT_PLAIN);
+
+        // the URL itself can be too long to show, i.e. for base64 images
+        Ok(Self::new(
+            format!("data:{}", mime.essence_str()),
+            data,
+            mime,
+        ))
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn mime(&self) -> &Mime {
+        &self.mime
+    }
+
+    // Write the data to a temporary file with an extension for its mime
+    // Files are named after a hash of the data, so opening it again reuses the file
+    // Files left from earlier runs are removed once they are old enough
+    pub fn write(&self) -> Result<PathBuf> {
+        self.write_in(&temp_dir()?)
+    }
+
+    fn write_in(&self, dir: &Path) -> Result<PathBuf> {
+        prune(dir, MAX_AGE);
+
+        let hash = Sha256::digest(&self.data)
+            .iter()
+            .take(8)
+            .map(|byte| format!("{:02x}", byte))
+            .collect::<String>();
+        let path = match mime_db::extension(self.mime.essence_str()) {
+            Some(ext) => dir.join(format!("{}.{}", hash, ext)),
+            None => dir.join(hash),
+        };
+
+        fs::write(&path, &self.data)?;
+        Ok(path)
+    }
+}
+
+// $XDG_RUNTIME_DIR/handlr, or a directory only the user can read
+// in the system's temporary directory if there is no runtime directory
+fn temp_dir() -> Result<PathBuf> {
+    let xdg = xdg::BaseDirectories::with_prefix("handlr")?;
+    if xdg.has_runtime_directory() {
+        return Ok(xdg.create_runtime_directory("")?);
+    }
+
+    // named after the user, since the temporary directory is shared
+    let uid = unsafe { libc::getuid() };
+    let dir = std::env::temp_dir().join(format!("handlr-{}", uid));
+    match fs::DirBuilder::new().mode(0o700).create(&dir) {
+        Err(e) if e.kind() != std::io::ErrorKind::AlreadyExists => {
+            return Err(Error::from(e))
+        }
+        _ => {}
+    }
+
+    // someone else may have created it first to read the files
+    let metadata = fs::symlink_metadata(&dir)?;
+    if !metadata.is_dir() || metadata.uid() != uid {
+        return Err(Error::from(std::io::Error::new(
+            std::io::ErrorKind::PermissionDenied,
+            format!("{} is not a directory owned by you", dir.display()),
+        )));
+    }
+    fs::set_permissions(&dir, fs::Permissions::from_mode(0o700))?;
+    Ok(dir)
+}
+
+// Remove files last written longer than `max_age` ago
+// Failing to is harmless, so errors are ignored
+fn prune(dir: &Path, max_age: Duration) {
+    let now = SystemTime::now();
+    let is_old = |entry: &fs::DirEntry| {
+        entry
+            .metadata()
+            .and_then(|metadata| metadata.modified())
+            .ok()
+            .and_then(|modified| now.duration_since(modified).ok())
+            .is_some_and(|age| age > max_age)
+    };
+
+    if let Ok(entries) = fs::read_dir(dir) {
+        for entry in entries.flatten().filter(is_old) {
+            let _ = fs::remove_file(entry.path());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn data_urls() -> Result<()> {
+        let content =
+            |url: &str| Content::from_data_url(&Url::parse(url).unwrap());
+
+        let html = content("data:text/html;charset=utf-8,%3Cb%3Ehi%3C%2Fb%3E")?;
+        assert_eq!(html.mime(), &mime::TEXT_HTML);
+        assert_eq!(html.data, b"<b>hi</b>");
+
+        let png = content("data:image/png;base64,iVBORw0K\nGgo")?;
+        assert_eq!(png.mime(), &mime::IMAGE_PNG);
+        assert_eq!(png.data, b"\x89PNG\r\n\x1a\n");
+        assert_eq!(png.name(), "data:image/png");
+
+        let text = content("data:,Hello%2C%20World%21#fragment")?;
+        assert_eq!(text.mime(), &mime::TEXT_PLAIN);
+        assert_eq!(text.data, b"Hello, World!");
+
+        assert!(content("data:;base64,not base64!").is_err());
+        assert!(content("data:text/plain").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn temporary_files() -> Result<()> {
+        let dir = std::env::temp_dir()
+            .join(format!("handlr-content-{}", std::process::id()));
+        fs::create_dir_all(&dir)?;
+
+        let content = Content::new("-", b"<b>hi</b>".to_vec(), mime::TEXT_HTML);
+        let path = content.write_in(&dir)?;
+
+        assert_eq!(path.parent(), Some(dir.as_path()));
+        assert_eq!(path.extension().unwrap(), "html");
+        assert_eq!(fs::read(&path)?, b"<b>hi</b>");
+        assert_eq!(content.write_in(&dir)?, path);
+
+        fs::write(dir.join("old.txt"), "")?;
+        prune(&dir, Duration::from_secs(60));
+        assert!(dir.join("old.txt").exists());
+        std::thread::sleep(Duration::from_millis(10));
+        prune(&dir, Duration::ZERO);
+        assert!(!dir.join("old.txt").exists());
+        assert!(!path.exists());
+        fs::remove_dir(&dir)?;
+
+        Ok(())
+    }
+}
@@ -1,21 +1,32 @@
-use crate::{Config, Error, ErrorKind, Result};
+use crate::{
+    multiplexer::{Multiplexer, MultiplexerMode},
+    terminal::{Terminal, TerminalOptions},
+    utils, Error, ErrorKind, Result, CONFIG,
+};
 use aho_corasick::AhoCorasick;
 use mime::Mime;
 use std::{
-    collections::HashMap,
+    collections::{HashMap, VecDeque},
     convert::TryFrom,
     ffi::OsString,
     path::{Path, PathBuf},
-    process::{Command, Stdio},
+    process::{Child, Command, Stdio},
     str::FromStr,
 };
 
+// Most bytes of arguments passed to a handler at once
+// Well below ARG_MAX on Linux and the BSDs, leaving room for the environment
+const ARGS_MAX: usize = 128 * 1024;
+
 #[derive(Debug, Clone, Default, PartialEq, Eq)]
 pub struct DesktopEntry {
     pub(crate) name: String,
     pub(crate) exec: String,
     pub(crate) file_name: OsString,
     pub(crate) terminal: bool,
+    pub(crate) icon: Option<String>,
+    // where to open it inside a multiplexer, if not the configured default
+    pub(crate) multiplexer: Option<MultiplexerMode>,
     pub(crate) mimes: Vec<Mime>,
     pub(crate) categories: HashMap<String, ()>,
 }
@@ -28,21 +39,43 @@ pub enum Mode {
 
 impl DesktopEntry {
     pub fn exec(&self, mode: Mode, arguments: Vec<String>) -> Result<()> {
-        let supports_multiple =
-            self.exec.contains("%F") || self.exec.contains("%U");
-        if arguments.is_empty() {
-            self.exec_inner(vec![])?
-        } else if supports_multiple || mode == Mode::Launch {
-            self.exec_inner(arguments)?;
-        } else {
-            for arg in arguments {
-                self.exec_inner(vec![arg])?;
+        let limit = CONFIG.process_limit.unwrap_or(usize::MAX).max(1);
+        let mut running: VecDeque<Child> = VecDeque::new();
+
+        for args in self.invocations(mode, arguments) {
+            // wait for the oldest process before starting one too many
+            if running.len() >= limit {
+                if let Some(mut child) = running.pop_front() {
+                    child.wait()?;
+                }
             }
-        };
+            running.extend(self.exec_inner(args)?);
+        }
 
         Ok(())
     }
-    fn exec_inner(&self, args: Vec<String>) -> Result<()> {
+
+    // Arguments of every process started to open or launch with the arguments
+    // Handlers taking several files get as many as fit in one command line
+    pub fn invocations(
+        &self,
+        mode: Mode,
+        arguments: Vec<String>,
+    ) -> Vec<Vec<String>> {
+        let supports_multiple =
+            self.exec.contains("%F") || self.exec.contains("%U");
+
+        if arguments.is_empty() || mode == Mode::Launch {
+            vec![arguments]
+        } else if supports_multiple {
+            batch(arguments, ARGS_MAX.saturating_sub(self.exec.len()))
+        } else {
+            arguments.into_iter().map(|arg| vec![arg]).collect()
+        }
+    }
+
+    // Returns the process if it keeps running in the background
+    fn exec_inner(&self, args: Vec<String>) -> Result<Option<Child>> {
         let mut cmd = {
             let (cmd, args) = self.get_cmd(args)?;
             let mut cmd = Command::new(cmd);
@@ -50,13 +83,14 @@ impl DesktopEntry {
             cmd
         };
 
-        if self.terminal && atty::is(atty::Stream::Stdout) {
+        if self.terminal && utils::use_current_terminal() {
             cmd.spawn()?.wait()?;
+            Ok(None)
         } else {
-            cmd.stdout(Stdio::null()).stderr(Stdio::null()).spawn()?;
+            Ok(Some(
+                cmd.stdout(Stdio::null()).stderr(Stdio::null()).spawn()?,
+            ))
         }
-
-        Ok(())
     }
     pub fn get_cmd(&self, args: Vec<String>) -> Result<(String, Vec<String>)> {
         let special =
@@ -91,20 +125,62 @@ impl DesktopEntry {
             exec.extend_from_slice(&args);
         }
 
+        if !self.terminal {
+            return Ok((exec.remove(0), exec));
+        }
+
+        let multiplexer = Multiplexer::detect().and_then(|multiplexer| {
+            multiplexer.command(
+                self.multiplexer.unwrap_or(CONFIG.multiplexer),
+                Some(self.name.as_str()),
+                exec.clone(),
+            )
+        });
+
+        // Inside a multiplexer, the entry can be opened in a new window or pane of it instead
+        if let Some(multiplexer) = multiplexer {
+            exec = multiplexer;
+        }
         // If the entry expects a terminal (emulator), but this process is not running in one, we
         // launch a new one.
-        if self.terminal && !atty::is(atty::Stream::Stdout) {
-            exec = shlex::split(&Config::terminal()?)
-                .unwrap()
-                .into_iter()
-                .chain(exec)
-                .collect();
+        else if !utils::use_current_terminal() {
+            let app_id = Path::new(&self.file_name)
+                .file_stem()
+                .map(|stem| stem.to_string_lossy());
+            let options = TerminalOptions {
+                hold: CONFIG.term_hold,
+                app_id: app_id.as_deref().filter(|id| !id.is_empty()),
+                title: Some(self.name.as_str()).filter(|name| !name.is_empty()),
+            };
+            exec = Terminal::find()?.command(&options, exec);
         }
 
         Ok((exec.remove(0), exec))
     }
 }
 
+// Split arguments into batches taking at most `budget` bytes of the command line,
+// counting each with its terminating NUL and pointer like execve does
+// An argument too large on its own still gets a batch of its own
+fn batch(arguments: Vec<String>, budget: usize) -> Vec<Vec<String>> {
+    let mut batches: Vec<Vec<String>> = Vec::new();
+    let mut used = 0;
+
+    for arg in arguments {
+        let size = arg.len() + 1 + std::mem::size_of::<usize>();
+        match batches.last_mut() {
+            Some(batch) if used + size <= budget => batch.push(arg),
+            _ => {
+                batches.push(vec![arg]);
+                used = 0;
+            }
+        }
+        used += size;
+    }
+
+    batches
+}
+
 fn parse_file(path: &Path) -> Option<DesktopEntry> {
     let raw_entry = freedesktop_entry_parser::parse_entry(path).ok()?;
     let section = raw_entry.section("Desktop Entry");
@@ -114,7 +190,7 @@ fn parse_file(path: &Path) -> Option<DesktopEntry> {
         ..Default::default()
     };
 
-    for attr in section.attrs().into_iter().filter(|a| a.has_value()) {
+    for attr in section.attrs().filter(|a| a.has_value()) {
         match attr.name {
             "Name" if entry.name.is_empty() => {
                 entry.name = attr.value.unwrap().into();
@@ -129,6 +205,7 @@ fn parse_file(path: &Path) -> Option<DesktopEntry> {
                     .collect::<Vec<_>>();
             }
             "Terminal" => entry.terminal = attr.value.unwrap() == "true",
+            "Icon" => entry.icon = attr.value.map(String::from),
             "Categories" => {
                 entry.categories = attr
                     .value
@@ -167,4 +244,37 @@ mod tests {
         assert_eq!(entry.mimes[0].essence_str(), "audio/mp3");
         assert_eq!(entry.mimes[1].essence_str(), "audio/ogg");
     }
+
+    #[test]
+    fn invocations() {
+        let entry = |exec: &str| DesktopEntry {
+            exec: exec.to_owned(),
+            ..Default::default()
+        };
+        let args = |n: usize| {
+            (0..n).map(|i| format!("{:04}.png", i)).collect::<Vec<_>>()
+        };
+
+        assert_eq!(entry("imv %f").invocations(Mode::Open, args(3)).len(), 3);
+        assert_eq!(entry("imv %F").invocations(Mode::Open, args(3)).len(), 1);
+        assert_eq!(
+            entry("imv %f").invocations(Mode::Launch, args(3)),
+            vec![args(3)]
+        );
+        assert_eq!(
+            entry("imv %F").invocations(Mode::Open, vec![]),
+            vec![Vec::<String>::new()]
+        );
+
+        // 10,000 arguments of 8 bytes, 9 with their NUL, and 8 byte pointers
+        let batches = entry("imv %F").invocations(Mode::Open, args(10_000));
+        assert!(batches.len() > 1);
+        assert!(batches.iter().all(|b| b.len() * 17 <= ARGS_MAX));
+        assert_eq!(batches.concat(), args(10_000));
+
+        assert_eq!(
+            batch(vec!["a".repeat(100), "b".into(), "c".into()], 20),
+            vec![vec!["a".repeat(100)], vec!["b".into(), "c".into()]]
+        );
+    }
 }
@@ -1,6 +1,7 @@
 use crate::{
+    apps::{CommandHandler, RegexHandler},
     common::{DesktopEntry, ExecMode},
-    Error, ErrorKind, RegexHandler, Result,
+    Error, ErrorKind, Result,
 };
 use std::{
     convert::TryFrom, ffi::OsString, fmt::Display, path::PathBuf, str::FromStr,
@@ -39,7 +40,10 @@ impl Handler {
         Ok(Self(name))
     }
     pub fn get_entry(&self) -> Result<DesktopEntry> {
-        DesktopEntry::try_from(Self::get_path(&self.0).unwrap())
+        let path = Self::get_path(&self.0).ok_or_else(|| {
+            ErrorKind::NotFound(self.0.to_string_lossy().into())
+        })?;
+        DesktopEntry::try_from(path)
     }
     pub fn launch(&self, args: Vec<String>) -> Result<()> {
         self.get_entry()?.exec(ExecMode::Launch, args)
@@ -49,17 +53,59 @@ impl Handler {
     }
 }
 
-#[derive(PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum GenericHandler {
     Handler(Handler),
     RegexHandler(RegexHandler),
+    CommandHandler(CommandHandler),
+}
+
+impl Display for GenericHandler {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GenericHandler::Handler(handler) => handler.fmt(f),
+            GenericHandler::RegexHandler(handler) => {
+                f.write_str(handler.name())
+            }
+            GenericHandler::CommandHandler(handler) => {
+                f.write_str(handler.name())
+            }
+        }
+    }
 }
 
 impl GenericHandler {
+    // name shown to the user when selecting a handler
+    pub fn name(&self) -> Result<String> {
+        Ok(match self {
+            GenericHandler::Handler(handler) => handler.get_entry()?.name,
+            GenericHandler::RegexHandler(handler) => handler.name().to_owned(),
+            GenericHandler::CommandHandler(handler) => {
+                handler.name().to_owned()
+            }
+        })
+    }
+    pub fn get_entry(&self) -> Result<DesktopEntry> {
+        Ok(match self {
+            GenericHandler::Handler(handler) => handler.get_entry()?,
+            GenericHandler::RegexHandler(handler) => handler.get_entry(),
+            GenericHandler::CommandHandler(handler) => handler.get_entry(),
+        })
+    }
+    pub fn launch(&self, args: Vec<String>) -> Result<()> {
+        match self {
+            GenericHandler::Handler(handler) => handler.launch(args),
+            GenericHandler::RegexHandler(handler) => {
+                handler.get_entry().exec(ExecMode::Launch, args)
+            }
+            GenericHandler::CommandHandler(handler) => handler.launch(args),
+        }
+    }
     pub fn open(&self, args: Vec<String>) -> Result<()> {
         match self {
             GenericHandler::Handler(handler) => handler.open(args),
             GenericHandler::RegexHandler(handler) => handler.open(args),
+            GenericHandler::CommandHandler(handler) => handler.open(args),
         }
     }
 }
@@ -52,6 +52,26 @@ impl TryFrom<&Path> for MimeType {
     }
 }
 
+// Guess the mime of data from its content, using the magic rules of shared-mime-info
+// Data no rule matches is plain text if it has no control characters
+impl TryFrom<&[u8]> for MimeType {
+    type Error = Error;
+    fn try_from(data: &[u8]) -> Result<Self> {
+        let db = xdg_mime::SharedMimeInfo::new();
+        let looks_like_text = !data.is_empty()
+            && !data
+                .iter()
+                .take(128)
+                .any(|c| c.is_ascii_control() && !c.is_ascii_whitespace());
+
+        db.get_mime_type_for_data(data)
+            .and_then(|(mime, _)| mime_to_option(&db, mime))
+            .or_else(|| looks_like_text.then_some(mime::TEXT_PLAIN))
+            .map(Self)
+            .ok_or_else(|| Error::from(ErrorKind::Ambiguous("-".into())))
+    }
+}
+
 fn mime_to_option(db: &xdg_mime::SharedMimeInfo, mime: Mime) -> Option<Mime> {
     let application_zerosize: Mime = "application/x-zerosize".parse().unwrap();
 
@@ -64,6 +84,12 @@ fn mime_to_option(db: &xdg_mime::SharedMimeInfo, mime: Mime) -> Option<Mime> {
     }
 }
 
+// Whether a mime matches a pattern that may have a wildcard subtype like video/*
+pub fn mime_matches(pattern: &Mime, mime: &Mime) -> bool {
+    pattern.type_() == mime.type_()
+        && (pattern.subtype() == "*" || pattern.subtype() == mime.subtype())
+}
+
 // Mime derived from user input: extension(.pdf) or type like image/jpg
 #[derive(Debug)]
 pub struct MimeOrExtension(pub Mime);
@@ -133,6 +159,19 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn from_content() -> Result<()> {
+        let mime = |data: &[u8]| MimeType::try_from(data).map(|m| m.0);
+
+        assert_eq!(mime(b"<!DOCTYPE html><html></html>")?, "text/html");
+        assert_eq!(mime(b"%PDF-1.7\n")?, mime::APPLICATION_PDF);
+        assert_eq!(mime(b"just some words\n")?, mime::TEXT_PLAIN);
+        mime(b"\x00\x01\x02binary").unwrap_err();
+        mime(b"").unwrap_err();
+
+        Ok(())
+    }
+
     #[test]
     fn from_ext() -> Result<()> {
         assert_eq!(".mp3".parse::<MimeOrExtension>()?.0, "audio/mpeg");
@@ -1,3 +1,4 @@
+mod content;
 mod db;
 mod desktop_entry;
 mod handler;
@@ -5,7 +6,8 @@ mod mime_types;
 mod path;
 
 pub use self::db::autocomplete as db_autocomplete;
+pub use content::Content;
 pub use desktop_entry::{DesktopEntry, Mode as ExecMode};
 pub use handler::{GenericHandler, Handler};
-pub use mime_types::{MimeOrExtension, MimeType};
-pub use path::{mime_table, UserPath};
+pub use mime_types::{mime_matches, MimeOrExtension, MimeType};
+pub use path::{mime_table, with_stdin, UserPath};
@@ -2,17 +2,24 @@ use ascii_table::AsciiTable;
 use mime::Mime;
 use url::Url;
 
-use crate::{common::MimeType, Error, ErrorKind, Result};
+use crate::{
+    common::{Content, MimeType},
+    Error, ErrorKind, Result,
+};
 use std::{
     convert::TryFrom,
+    ffi::OsString,
     fmt::{Display, Formatter},
-    path::PathBuf,
+    io::Read,
+    os::unix::ffi::OsStringExt,
+    path::{Component, Path, PathBuf},
     str::FromStr,
 };
 
 pub enum UserPath {
     Url(Url),
     File(PathBuf),
+    Content(Content),
 }
 
 impl UserPath {
@@ -20,9 +27,119 @@ impl UserPath {
         Ok(match self {
             Self::Url(url) => Ok(url.into()),
             Self::File(f) => MimeType::try_from(f.as_path()),
+            Self::Content(c) => Ok(MimeType(c.mime().clone())),
         }?
         .0)
     }
+
+    // The path or URL passed to handlers
+    // Content is written to a temporary file first
+    pub fn argument(&self) -> Result<String> {
+        match self {
+            Self::Content(c) => Ok(c.write()?.to_string_lossy().into_owned()),
+            path => Ok(path.to_string()),
+        }
+    }
+
+    // whether this is `-`, standing for content read from stdin
+    pub fn is_stdin(&self) -> bool {
+        matches!(self, Self::File(f) if f.as_os_str() == "-")
+    }
+
+    // A canonical form of the path for matching against
+    // Files become absolute paths and URLs are percent-decoded
+    pub fn normalized(&self) -> String {
+        match self {
+            Self::File(f) => normalize_file(f).to_string_lossy().into_owned(),
+            Self::Url(u) => percent_encoding::percent_decode_str(u.as_str())
+                .decode_utf8_lossy()
+                .into_owned(),
+            Self::Content(c) => c.name().to_owned(),
+        }
+    }
+}
+
+// Read the content for `-` from stdin,
+// or the paths after the other paths if asked to read them from it instead
+pub fn with_stdin(
+    mut paths: Vec<UserPath>,
+    stdin: bool,
+    null: bool,
+) -> Result<Vec<UserPath>> {
+    let dashes = paths.iter().filter(|path| path.is_stdin()).count();
+
+    match (dashes, stdin) {
+        (0, false) => {}
+        (0, true) => {
+            paths.extend(read_paths(std::io::stdin().lock(), null)?);
+        }
+        (1, false) => {
+            if let Some(path) = paths.iter_mut().find(|path| path.is_stdin()) {
+                *path = UserPath::Content(Content::from_stdin()?);
+            }
+        }
+        // stdin can only be read once
+        _ => {
+            return Err(Error::from(ErrorKind::BadPath(
+                "- can only be given once, and not with --stdin".to_owned(),
+            )))
+        }
+    }
+
+    Ok(paths)
+}
+
+// Paths separated by newlines or NUL characters, skipping empty ones
+// Paths that are not valid UTF-8 are read as they are
+fn read_paths<R: Read>(mut reader: R, null: bool) -> Result<Vec<UserPath>> {
+    let mut input = Vec::new();
+    reader.read_to_end(&mut input)?;
+
+    input
+        .split(|b| *b == if null { b'\0' } else { b'\n' })
+        .filter(|path| !path.is_empty())
+        .map(|path| match std::str::from_utf8(path) {
+            Ok(path) => UserPath::from_str(path),
+            Err(_) => Ok(UserPath::File(PathBuf::from(OsString::from_vec(
+                path.to_vec(),
+            )))),
+        })
+        .collect()
+}
+
+fn normalize_file(path: &Path) -> PathBuf {
+    if let Ok(canonical) = path.canonicalize() {
+        return canonical;
+    }
+
+    // The file does not exist, so make the path absolute by hand
+    let path = match path.strip_prefix("~") {
+        Ok(rest) => match std::env::var_os("HOME") {
+            Some(home) => PathBuf::from(home).join(rest),
+            None => path.to_owned(),
+        },
+        Err(_) => path.to_owned(),
+    };
+
+    let path = if path.is_relative() {
+        std::env::current_dir()
+            .map(|cwd| cwd.join(&path))
+            .unwrap_or(path)
+    } else {
+        path
+    };
+
+    path.components()
+        .fold(PathBuf::new(), |mut normalized, component| {
+            match component {
+                Component::CurDir => {}
+                Component::ParentDir => {
+                    normalized.pop();
+                }
+                c => normalized.push(c),
+            }
+            normalized
+        })
 }
 
 impl FromStr for UserPath {
@@ -36,6 +153,9 @@ impl FromStr for UserPath {
 
                 Self::File(path)
             }
+            Ok(url) if url.scheme() == "data" => {
+                Self::Content(Content::from_data_url(&url)?)
+            }
             Ok(url) => Self::Url(url),
             _ => Self::File(PathBuf::from(s)),
         };
@@ -49,6 +169,7 @@ impl Display for UserPath {
         match self {
             Self::File(f) => fmt.write_str(&f.to_string_lossy()),
             Self::Url(u) => fmt.write_str(u.as_ref()),
+            Self::Content(c) => fmt.write_str(c.name()),
         }
     }
 }
@@ -82,3 +203,54 @@ pub fn mime_table(paths: &[UserPath], output_json: bool) -> Result<(), Error> {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalized() -> Result<()> {
+        let cwd = std::env::current_dir()?;
+
+        assert_eq!(
+            UserPath::from_str("./tests/../tests/cat")?.normalized(),
+            cwd.join("tests/cat").to_string_lossy()
+        );
+        assert_eq!(
+            UserPath::from_str("does/not/./exist.txt")?.normalized(),
+            cwd.join("does/not/exist.txt").to_string_lossy()
+        );
+        assert_eq!(
+            UserPath::from_str("https://example.com/a%20b")?.normalized(),
+            "https://example.com/a b"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn paths_from_stdin() -> Result<()> {
+        let read = |input: &[u8], null| -> Result<Vec<String>> {
+            Ok(read_paths(input, null)?
+                .iter()
+                .map(|path| path.to_string())
+                .collect())
+        };
+
+        assert_eq!(
+            read(b"a b.txt\nhttps://example.com\n\nfile:///tmp/c\n", false)?,
+            vec!["a b.txt", "https://example.com/", "/tmp/c"]
+        );
+        assert_eq!(
+            read(b"line\nbreak.txt\0other.pdf\0", true)?,
+            vec!["line\nbreak.txt", "other.pdf"]
+        );
+        assert_eq!(
+            read(b"not\xffutf8.txt", false)?,
+            vec!["not\u{fffd}utf8.txt"]
+        );
+        assert!(UserPath::from_str("-")?.is_stdin());
+
+        Ok(())
+    }
+}
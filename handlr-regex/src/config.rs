@@ -1,126 +1,682 @@
 use crate::{
-    apps::{ConfigHandler, SystemApps},
-    common::Handler,
+    apps::{ConfigHandler, ConfigMimeHandler},
+    multiplexer::MultiplexerMode,
+    picker::BuiltinSelector,
+    project,
+    terminal::TerminalArgs,
     Error, ErrorKind, Result,
 };
 use mime::Mime;
-use once_cell::sync::Lazy;
+use once_cell::sync::{Lazy, OnceCell};
 use serde::{Deserialize, Serialize};
-use std::str::FromStr;
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::atomic::{AtomicBool, Ordering},
+};
+use toml::Value;
 
 pub static CONFIG: Lazy<Config> = Lazy::new(Config::load);
 
+// config file given with --config, used instead of the user's config file
+static CONFIG_PATH: OnceCell<PathBuf> = OnceCell::new();
+
+// whether per-directory config files are used,
+// which only commands that resolve handlers do
+static PROJECT_LAYERS: AtomicBool = AtomicBool::new(false);
+
 #[derive(Serialize, Deserialize)]
 #[serde(default)]
 pub struct Config {
     pub enable_selector: bool,
+    // enable_selector for mimes, which may be wildcards like image/*
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub selector_mimes: BTreeMap<String, bool>,
     pub selector: String,
+    // send icons to the selector in the extended dmenu format of rofi
+    pub selector_icons: bool,
+    // always pick from every capable application when opening paths
+    pub open_with_menu: bool,
+    // when to use the built-in picker instead of the selector
+    pub builtin_selector: BuiltinSelector,
+    // offer handlers picked often and recently for a mime first
+    pub selector_history: bool,
+    // seconds after which the handler picked last for a mime is picked
+    pub selector_timeout: Option<u64>,
     #[serde(skip_serializing_if = "Vec::is_empty")]
     pub handlers: Vec<ConfigHandler>,
-    term_exec_args: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub mime_handlers: Vec<ConfigMimeHandler>,
+    // commands run in the current terminal when there is no graphical session
+    // keyed by mime, which may be a wildcard like image/*
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub tty_handlers: BTreeMap<String, String>,
+    // fall back to $BROWSER, $VISUAL, $EDITOR and $TERMINAL
+    pub env_fallbacks: bool,
+    // arguments for running commands in terminals without a profile,
+    // or in every terminal when changed from the default
+    pub term_exec_args: Option<String>,
+    // keep terminals opened for handlers open after they exit
+    pub term_hold: bool,
+    // most processes a handler runs at the same time when opening many files
+    pub process_limit: Option<usize>,
+    // ask before starting more processes than this to open paths, 0 for never
+    pub confirm_processes: Option<usize>,
+    // where to open terminal handlers inside tmux, zellij or screen
+    pub multiplexer: MultiplexerMode,
+    // arguments of terminal emulators by desktop ID or program
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub terminals: BTreeMap<String, TerminalArgs>,
 }
 
 impl Default for Config {
     fn default() -> Self {
         Config {
             enable_selector: false,
+            selector_mimes: BTreeMap::new(),
             selector: "rofi -dmenu -i -p 'Open With: '".into(),
+            builtin_selector: BuiltinSelector::Auto,
+            selector_icons: false,
+            open_with_menu: false,
+            selector_history: true,
+            selector_timeout: None,
             handlers: Vec::new(),
+            mime_handlers: Vec::new(),
+            tty_handlers: BTreeMap::new(),
+            env_fallbacks: false,
             // Required for many xterm-compatible terminal emulators
             // Unfortunately, messes up emulators that don't accept it
             term_exec_args: Some("-e".into()),
+            term_hold: false,
+            process_limit: None,
+            confirm_processes: None,
+            multiplexer: MultiplexerMode::Off,
+            terminals: BTreeMap::new(),
         }
     }
 }
 
 impl Config {
-    pub fn terminal() -> Result<String> {
-        let terminal_entry = crate::apps::APPS
-            .get_handler(&Mime::from_str("x-scheme-handler/terminal").unwrap())
-            .ok()
-            .and_then(|h| h.get_entry().ok());
-
-        terminal_entry
-            .or_else(|| {
-                let entry = SystemApps::get_entries()
-                    .ok()?
-                    .find(|(_handler, entry)| {
-                        entry.categories.contains_key("TerminalEmulator")
-                    })?;
-
-                crate::utils::notify(
-                    "handlr",
-                    &format!(
-                        "Guessed terminal emulator: {}.\n\nIf this is wrong, use `handlr set x-scheme-handler/terminal` to update it.",
-                        entry.0.to_string_lossy()
-                    )
-                ).ok()?;
-
-                let mut apps = (*crate::apps::APPS).clone();
-                apps.set_handler(
-                    Mime::from_str("x-scheme-handler/terminal").unwrap(),
-                    Handler::assume_valid(entry.0),
-                );
-                apps.save().ok()?;
-
-                Some(entry.1)
-            })
-            .map(|e| {
-                let mut exec = e.exec;
+    pub fn load() -> Self {
+        Self::try_load().unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            std::process::exit(1)
+        })
+    }
+
+    // Load the config from all of its layers, from least to most important:
+    // 1. handlr/handlr.toml in $XDG_CONFIG_DIRS (i.e. /etc/xdg)
+    // 2. the user's config file or the one given with --config
+    // 3. trusted .handlr.toml files in the current directory and its ancestors,
+    //    if enabled with use_project_layers
+    // 4. HANDLR_* environment variables
+    pub fn try_load() -> Result<Self> {
+        let mut config = Value::Table(toml::value::Table::new());
+
+        let system_paths = Self::system_paths()?;
+        for path in system_paths.iter() {
+            merge(&mut config, read_layer(path)?);
+        }
+
+        let path = Self::path()?;
+        if path.exists() {
+            merge(&mut config, read_layer(&path)?);
+        } else if CONFIG_PATH.get().is_some() {
+            return Err(Error::from(ErrorKind::BadPath(
+                path.to_string_lossy().into(),
+            )));
+        } else if system_paths.is_empty() {
+            // create config if it doesn't exist
+            // skipped when there are site defaults so they are not overridden
+            Self::default().save()?;
+        }
+
+        if PROJECT_LAYERS.load(Ordering::Relaxed) {
+            for layer in project::layers()? {
+                merge(&mut config, layer);
+            }
+        }
+
+        merge(&mut config, env_overrides(std::env::vars()));
+
+        config
+            .try_into()
+            .map_err(|e| ErrorKind::InvalidConfig(e).into())
+    }
 
-                if let Some(opts) = &CONFIG.term_exec_args {
-                    exec.push(' ');
-                    exec.push_str(opts)
+    // Use the given config file instead of the user's config file
+    pub fn set_path(path: PathBuf) {
+        // only fails if already set, which is fine
+        let _ = CONFIG_PATH.set(path);
+    }
+
+    // Include per-directory config files when loading the config
+    // Left out otherwise, so e.g. completions do not ask to trust them
+    pub fn use_project_layers() {
+        PROJECT_LAYERS.store(true, Ordering::Relaxed);
+    }
+
+    // Path to the user's config file or the one given with --config
+    pub fn path() -> Result<PathBuf> {
+        match CONFIG_PATH.get() {
+            Some(path) => Ok(path.clone()),
+            None => Ok(xdg::BaseDirectories::with_prefix("handlr")?
+                .get_config_home()
+                .join("handlr.toml")),
+        }
+    }
+
+    // Site-wide config files, from least to most important
+    fn system_paths() -> Result<Vec<PathBuf>> {
+        Ok(xdg::BaseDirectories::with_prefix("handlr")?
+            .get_config_dirs()
+            .into_iter()
+            .rev()
+            .map(|dir| dir.join("handlr.toml"))
+            .filter(|path| path.exists())
+            .collect())
+    }
+
+    fn save(&self) -> Result<()> {
+        write_layer(&Self::path()?, &Value::try_from(self)?)
+    }
+
+    // The user's config file on its own
+    fn user_layer() -> Result<Value> {
+        let path = Self::path()?;
+        if path.exists() {
+            read_layer(&path)
+        } else {
+            Ok(Value::Table(toml::value::Table::new()))
+        }
+    }
+
+    // Print the path of the user's config file
+    pub fn show_path(output_json: bool) -> Result<()> {
+        let path = Self::path()?;
+        if output_json {
+            let system_paths = Self::system_paths()?
+                .iter()
+                .map(|p| p.to_string_lossy().into_owned())
+                .collect::<Vec<_>>();
+            println!(
+                "{}",
+                json::object! {
+                    path: path.to_string_lossy().as_ref(),
+                    system_paths: system_paths,
                 }
+            );
+        } else {
+            println!("{}", path.to_string_lossy());
+        }
+        Ok(())
+    }
 
-                exec
-            })
-            .ok_or(Error::from(ErrorKind::NoTerminal))
+    // Print the effective config
+    pub fn show(&self, output_json: bool) -> Result<()> {
+        // going through Value makes sure tables are written after other values
+        let config = Value::try_from(self)?;
+        if output_json {
+            println!("{}", to_json(&config));
+        } else {
+            print!("{}", toml::to_string(&config)?);
+        }
+        Ok(())
     }
-    pub fn load() -> Self {
-        confy::load("handlr").unwrap()
+
+    // Print the effective value of a dotted key like `handlers.0.exec`
+    pub fn show_key(&self, key: &str, output_json: bool) -> Result<()> {
+        let config = Value::try_from(self)?;
+        let value = get_key(&config, key)
+            .ok_or_else(|| ErrorKind::UnknownConfigKey(key.to_owned()))?;
+
+        if output_json {
+            println!("{}", to_json(value));
+        } else {
+            match value {
+                Value::String(s) => println!("{}", s),
+                Value::Table(_) => print!("{}", toml::to_string(value)?),
+                value => println!("{}", value),
+            }
+        }
+        Ok(())
     }
 
-    pub fn select<O: Iterator<Item = String>>(
-        &self,
-        mut opts: O,
-    ) -> Result<String> {
-        use itertools::Itertools;
-        use std::{
-            io::prelude::*,
-            process::{Command, Stdio},
-        };
+    // Set a dotted key in the user's config file
+    // The file is only written if the result is a valid config
+    pub fn set_key(key: &str, value: &str) -> Result<()> {
+        let mut layer = Self::user_layer()?;
+        set_key(&mut layer, key, parse_value(value))?;
 
-        let process = {
-            let mut split = shlex::split(&self.selector).unwrap();
-            let (cmd, args) = (split.remove(0), split);
-            Command::new(cmd)
-                .args(args)
-                .stdin(Stdio::piped())
-                .stdout(Stdio::piped())
-                .spawn()?
-        };
+        let config: Config =
+            layer.clone().try_into().map_err(ErrorKind::InvalidConfig)?;
+
+        // unknown keys are dropped when deserializing
+        if get_key(&Value::try_from(&config)?, key).is_none() {
+            return Err(Error::from(ErrorKind::UnknownConfigKey(
+                key.to_owned(),
+            )));
+        }
 
-        let output = {
-            process
-                .stdin
-                .ok_or_else(|| ErrorKind::Selector(self.selector.clone()))?
-                .write_all(opts.join("\n").as_bytes())?;
+        let errors = config.problems();
+        if !errors.is_empty() {
+            errors.iter().for_each(|e| eprintln!("{}", e));
+            return Err(Error::from(ErrorKind::BadConfigCount(errors.len())));
+        }
 
-            let mut output = String::with_capacity(24);
+        write_layer(&Self::path()?, &layer)
+    }
 
-            process
-                .stdout
-                .ok_or_else(|| ErrorKind::Selector(self.selector.clone()))?
-                .read_to_string(&mut output)?;
+    // Edit the user's config file with $VISUAL or $EDITOR
+    // Changes are made to a copy which only replaces the config if it is valid
+    // and the editor exits successfully
+    pub fn edit() -> Result<()> {
+        let path = Self::path()?;
+        let mut copy = path.clone().into_os_string();
+        copy.push(".edit");
+        let copy = PathBuf::from(copy);
 
-            output.trim_end().to_owned()
+        // keep the changes from an edit that turned out to be invalid
+        if !copy.exists() {
+            write_layer(&copy, &Self::user_layer()?)?;
+        }
+
+        let editor = std::env::var("VISUAL")
+            .or_else(|_| std::env::var("EDITOR"))
+            .unwrap_or_else(|_| String::from("vi"));
+        let mut editor = shlex::split(&editor)
+            .filter(|e| !e.is_empty())
+            .ok_or_else(|| ErrorKind::BadPath(editor.clone()))?;
+
+        let status = std::process::Command::new(editor.remove(0))
+            .args(editor)
+            .arg(&copy)
+            .status()?;
+        if !status.success() {
+            return Err(Error::from(ErrorKind::EditorFailed(status)));
+        }
+
+        read_layer(&copy)?
+            .try_into::<Config>()
+            .map_err(|e| ErrorKind::BadConfig(copy.clone(), e))?;
+
+        std::fs::rename(&copy, &path)?;
+        Ok(())
+    }
+
+    // Trust a per-directory config file
+    // Defaults to the closest one to the current directory
+    pub fn trust(path: Option<PathBuf>) -> Result<()> {
+        let path = match path {
+            Some(path) => path,
+            None => project::find(&std::env::current_dir()?).pop().ok_or_else(
+                || ErrorKind::BadPath(project::PROJECT_CONFIG.to_owned()),
+            )?,
+        };
+        project::trust(&path)
+    }
+
+    // Whether to use the selector for a mime when it has several handlers
+    pub fn selector_enabled(&self, mime: &Mime) -> bool {
+        let wildcard = format!("{}/*", mime.type_());
+
+        self.selector_mimes
+            .get(mime.essence_str())
+            .or_else(|| self.selector_mimes.get(&wildcard))
+            .copied()
+            .unwrap_or(self.enable_selector)
+    }
+
+    // Check every layer of the config and all of the handlers in it
+    pub fn check(output_json: bool) -> Result<()> {
+        let errors = match Self::try_load() {
+            Ok(config) => config.problems(),
+            Err(e) => vec![e.to_string()],
         };
 
-        if output.is_empty() {
-            Err(Error::from(ErrorKind::Cancelled))
+        if output_json {
+            println!(
+                "{}",
+                json::object! {
+                    valid: errors.is_empty(),
+                    errors: errors.clone(),
+                }
+            );
+            // the output says whether the config is valid,
+            // another error message would keep it from being parsed
+            if !errors.is_empty() {
+                return Err(Error::from(ErrorKind::Reported));
+            }
+            return Ok(());
+        }
+
+        errors.iter().for_each(|e| eprintln!("{}", e));
+
+        if errors.is_empty() {
+            Ok(())
         } else {
-            Ok(output)
+            Err(Error::from(ErrorKind::BadConfigCount(errors.len())))
+        }
+    }
+
+    // Problems that are only found once values are used,
+    // like handlers with invalid regexes
+    fn problems(&self) -> Vec<String> {
+        self.handlers
+            .iter()
+            .enumerate()
+            .filter_map(|(i, handler)| {
+                let e = handler.compile_regex().err()?;
+                Some(format!("handlers.{}: {}", i, e))
+            })
+            .chain(self.mime_handlers.iter().enumerate().filter_map(
+                |(i, handler)| {
+                    let e = handler.compile().err()?;
+                    Some(format!("mime_handlers.{}: {}", i, e))
+                },
+            ))
+            .chain(self.tty_handlers.keys().filter_map(|mime| {
+                let e = Mime::from_str(mime).err()?;
+                Some(format!("tty_handlers.{}: {}", mime, e))
+            }))
+            .chain(self.selector_mimes.keys().filter_map(|mime| {
+                let e = Mime::from_str(mime).err()?;
+                Some(format!("selector_mimes.{}: {}", mime, e))
+            }))
+            .collect()
+    }
+}
+
+fn read_layer(path: &Path) -> Result<Value> {
+    toml::from_str(&std::fs::read_to_string(path)?)
+        .map_err(|e| ErrorKind::BadConfig(path.to_owned(), e).into())
+}
+
+fn write_layer(path: &Path, layer: &Value) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(path, toml::to_string(layer)?)?;
+    Ok(())
+}
+
+// Parse a value given by the user as TOML, falling back to a string
+fn parse_value(value: &str) -> Value {
+    toml::from_str::<toml::value::Table>(&format!("value = {}", value))
+        .ok()
+        .and_then(|mut t| t.remove("value"))
+        .unwrap_or_else(|| Value::String(value.to_owned()))
+}
+
+fn get_key<'a>(value: &'a Value, key: &str) -> Option<&'a Value> {
+    key.split('.').try_fold(value, |value, part| match value {
+        Value::Table(table) => table.get(part),
+        Value::Array(array) => array.get(part.parse::<usize>().ok()?),
+        _ => None,
+    })
+}
+
+fn set_key(value: &mut Value, key: &str, new: Value) -> Result<()> {
+    let unknown = || Error::from(ErrorKind::UnknownConfigKey(key.to_owned()));
+
+    let (parents, last) = match key.rsplit_once('.') {
+        Some((parents, last)) => (parents.split('.').collect(), last),
+        None => (Vec::new(), key),
+    };
+
+    let parent =
+        parents
+            .into_iter()
+            .try_fold(value, |value, part| match value {
+                Value::Table(table) => Some(
+                    table
+                        .entry(part)
+                        .or_insert_with(|| Value::Table(Default::default())),
+                ),
+                Value::Array(array) => {
+                    array.get_mut(part.parse::<usize>().ok()?)
+                }
+                _ => None,
+            });
+
+    match parent.ok_or_else(unknown)? {
+        Value::Table(table) => {
+            table.insert(last.to_owned(), new);
+        }
+        Value::Array(array) => {
+            *last
+                .parse::<usize>()
+                .ok()
+                .and_then(|i| array.get_mut(i))
+                .ok_or_else(unknown)? = new;
+        }
+        _ => return Err(unknown()),
+    }
+
+    Ok(())
+}
+
+fn to_json(value: &Value) -> json::JsonValue {
+    match value {
+        Value::String(s) => s.as_str().into(),
+        Value::Integer(i) => (*i).into(),
+        Value::Float(f) => (*f).into(),
+        Value::Boolean(b) => (*b).into(),
+        Value::Datetime(d) => d.to_string().into(),
+        Value::Array(array) => {
+            json::JsonValue::Array(array.iter().map(to_json).collect())
+        }
+        Value::Table(table) => {
+            let mut object = json::JsonValue::new_object();
+            for (key, value) in table {
+                object[key.as_str()] = to_json(value);
+            }
+            object
+        }
+    }
+}
+
+// Lists of handlers, which are concatenated when merging layers
+static MERGED_LISTS: &[&str] = &["handlers", "mime_handlers"];
+
+// Merge a config layer on top of another
+// Handler lists are concatenated with the upper layer's items first,
+// tables are merged and anything else is overridden
+fn merge(lower: &mut Value, upper: Value) {
+    match (lower, upper) {
+        (Value::Table(lower), Value::Table(upper)) => {
+            for (key, value) in upper {
+                match (lower.get_mut(&key), value) {
+                    (Some(Value::Array(lower)), Value::Array(mut upper))
+                        if MERGED_LISTS.contains(&key.as_str()) =>
+                    {
+                        upper.append(lower);
+                        *lower = upper;
+                    }
+                    (Some(lower), value) => merge_value(lower, value),
+                    (None, value) => {
+                        lower.insert(key, value);
+                    }
+                }
+            }
         }
+        (lower, upper) => *lower = upper,
+    }
+}
+
+// Merge nested values, where arrays like arguments are overridden as a whole
+fn merge_value(lower: &mut Value, upper: Value) {
+    match (lower, upper) {
+        (Value::Table(lower), Value::Table(upper)) => {
+            for (key, value) in upper {
+                match lower.get_mut(&key) {
+                    Some(lower) => merge_value(lower, value),
+                    None => {
+                        lower.insert(key, value);
+                    }
+                }
+            }
+        }
+        (lower, upper) => *lower = upper,
+    }
+}
+
+// Config layer from HANDLR_* environment variables, e.g. HANDLR_ENABLE_SELECTOR
+// Values are parsed as TOML if possible and used as strings otherwise
+fn env_overrides<I: Iterator<Item = (String, String)>>(vars: I) -> Value {
+    Value::Table(
+        vars.filter_map(|(name, value)| {
+            let key = name.strip_prefix("HANDLR_")?.to_lowercase();
+            let value = match parse_value(&value) {
+                Value::Table(_) | Value::Array(_) => Value::String(value),
+                parsed => parsed,
+            };
+            Some((key, value))
+        })
+        .collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn merge_layers() -> Result<()> {
+        let mut config: Value = toml::from_str(
+            r#"
+            enable_selector = true
+            selector = "fzf"
+            [[handlers]]
+            exec = "system %u"
+            regexes = ["system"]
+            [terminals."kitty.desktop"]
+            exec = ["--single-instance"]
+            hold = ["--hold"]
+            "#,
+        )?;
+
+        merge(
+            &mut config,
+            toml::from_str(
+                r#"
+                selector = "rofi -dmenu"
+                [[handlers]]
+                exec = "user %u"
+                regexes = ["user"]
+                [terminals."kitty.desktop"]
+                exec = ["-1"]
+                "#,
+            )?,
+        );
+
+        merge(
+            &mut config,
+            env_overrides(
+                vec![
+                    ("HANDLR_ENABLE_SELECTOR".into(), "false".into()),
+                    ("HANDLR_TERM_EXEC_ARGS".into(), "--".into()),
+                    ("NOT_HANDLR".into(), "ignored".into()),
+                ]
+                .into_iter(),
+            ),
+        );
+
+        let config: Config = config.try_into()?;
+
+        assert!(!config.enable_selector);
+        assert_eq!(config.selector, "rofi -dmenu");
+        assert_eq!(config.term_exec_args.as_deref(), Some("--"));
+        assert_eq!(config.handlers.len(), 2);
+        assert!(toml::to_string(&config.handlers[0])?.contains("user %u"));
+
+        // arguments are replaced rather than concatenated
+        let kitty = toml::to_string(&config.terminals["kitty.desktop"])?;
+        assert!(kitty.contains(r#"exec = ["-1"]"#));
+        assert!(kitty.contains(r#"hold = ["--hold"]"#));
+
+        Ok(())
+    }
+
+    #[test]
+    fn config_keys() -> Result<()> {
+        let mut config: Value = toml::from_str(
+            r#"
+            selector = "fzf"
+            [[handlers]]
+            exec = "mpv %u"
+            regexes = ["youtube"]
+            "#,
+        )?;
+
+        set_key(&mut config, "enable_selector", parse_value("true"))?;
+        set_key(&mut config, "handlers.0.exec", parse_value("vlc %u"))?;
+        set_key(&mut config, "selector", parse_value("rofi -dmenu"))?;
+        assert!(
+            set_key(&mut config, "selector.nested", parse_value("")).is_err()
+        );
+        assert!(
+            set_key(&mut config, "handlers.1.exec", parse_value("")).is_err()
+        );
+
+        assert_eq!(
+            get_key(&config, "enable_selector"),
+            Some(&Value::Boolean(true))
+        );
+        assert_eq!(
+            get_key(&config, "handlers.0.exec"),
+            Some(&Value::String("vlc %u".into()))
+        );
+        assert_eq!(
+            get_key(&config, "selector"),
+            Some(&Value::String("rofi -dmenu".into()))
+        );
+        assert_eq!(get_key(&config, "handlers.0.missing"), None);
+
+        assert_eq!(
+            to_json(get_key(&config, "handlers").unwrap()).dump(),
+            r#"[{"exec":"vlc %u","regexes":["youtube"]}]"#
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn selector_mimes() -> Result<()> {
+        let config: Config = toml::from_str(
+            r#"
+            enable_selector = true
+            [selector_mimes]
+            "image/*" = false
+            "image/svg+xml" = true
+            "#,
+        )?;
+        let enabled = |mime: &str| {
+            config.selector_enabled(&Mime::from_str(mime).unwrap())
+        };
+
+        assert!(enabled("text/html"));
+        assert!(!enabled("image/png"));
+        assert!(enabled("image/svg+xml"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn problems() -> Result<()> {
+        let config: Config = toml::from_str(
+            r#"
+            handlers = [{ exec = "x", regexes = ["("] }]
+            [tty_handlers]
+            "text/plain" = "less"
+            "#,
+        )?;
+        let problems = config.problems();
+
+        assert_eq!(problems.len(), 1);
+        assert!(problems[0].starts_with("handlers.0: "));
+
+        Ok(())
     }
 }
@@ -23,8 +23,18 @@ pub enum ErrorKind {
     Io(#[from] std::io::Error),
     #[error(transparent)]
     Xdg(#[from] xdg::BaseDirectoriesError),
+    #[error("malformed config file at {0}: {1}")]
+    BadConfig(std::path::PathBuf, toml::de::Error),
+    #[error("invalid config: {0}")]
+    InvalidConfig(toml::de::Error),
+    #[error("unknown config key '{0}'")]
+    UnknownConfigKey(String),
+    #[error("found {0} problem(s) in config")]
+    BadConfigCount(usize),
     #[error(transparent)]
-    Config(#[from] confy::ConfyError),
+    ConfigRead(#[from] toml::de::Error),
+    #[error(transparent)]
+    ConfigWrite(#[from] toml::ser::Error),
     #[error("no handlers found for '{0}'")]
     NotFound(String),
     #[error("could not figure out the mime type of '{0}'")]
@@ -37,10 +47,19 @@ pub enum ErrorKind {
     BadEntry(std::path::PathBuf),
     #[error(transparent)]
     BadRegex(#[from] regex::Error),
+    #[error(transparent)]
+    BadGlob(#[from] glob::PatternError),
     #[error("error spawning selector process '{0}'")]
     Selector(String),
+    #[error("could not open {0} path(s)")]
+    OpenFailed(usize),
     #[error("selection cancelled")]
     Cancelled,
+    // the problem was already shown in the output of the command
+    #[error("failed")]
+    Reported,
+    #[error("editor exited with {0}")]
+    EditorFailed(std::process::ExitStatus),
     #[error("Please specify the default terminal with handlr set x-scheme-handler/terminal")]
     NoTerminal,
     #[error("Bad path: {0}")]
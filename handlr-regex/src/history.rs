@@ -0,0 +1,168 @@
+use crate::{Error, Result};
+use std::{
+    fs,
+    io::Write,
+    path::PathBuf,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+// How long it takes for a choice to count half as much, in seconds
+const HALF_LIFE: f64 = 14.0 * 24.0 * 60.0 * 60.0;
+
+// Handlers picked with the selector, by mime
+// Stored as tab-separated lines of mime, handler, times picked and when it was last picked
+#[derive(Debug, Default)]
+pub struct History {
+    path: PathBuf,
+    entries: Vec<HistoryEntry>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct HistoryEntry {
+    mime: String,
+    handler: String,
+    count: u64,
+    last_used: u64,
+}
+
+impl History {
+    pub fn load() -> Result<Self> {
+        let path = xdg::BaseDirectories::with_prefix("handlr")?
+            .get_state_home()
+            .join("history");
+        Self::load_from(path)
+    }
+
+    fn load_from(path: PathBuf) -> Result<Self> {
+        let entries = match fs::read_to_string(&path) {
+            Ok(history) => history
+                .lines()
+                .filter_map(|line| {
+                    let mut fields = line.split('\t');
+                    Some(HistoryEntry {
+                        mime: fields.next()?.to_owned(),
+                        handler: fields.next()?.to_owned(),
+                        count: fields.next()?.parse().ok()?,
+                        last_used: fields.next()?.parse().ok()?,
+                    })
+                })
+                .collect(),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(Error::from(e)),
+        };
+
+        Ok(Self { path, entries })
+    }
+
+    pub fn save(&self) -> Result<()> {
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let mut file = fs::File::create(&self.path)?;
+        for entry in self.entries.iter() {
+            writeln!(
+                file,
+                "{}\t{}\t{}\t{}",
+                entry.mime, entry.handler, entry.count, entry.last_used
+            )?;
+        }
+        Ok(())
+    }
+
+    // Remember that the handler was picked for the mime
+    pub fn record(&mut self, mime: &str, handler: &str) {
+        self.record_at(mime, handler, now())
+    }
+
+    fn record_at(&mut self, mime: &str, handler: &str, time: u64) {
+        match self
+            .entries
+            .iter_mut()
+            .find(|e| e.mime == mime && e.handler == handler)
+        {
+            Some(entry) => {
+                entry.count += 1;
+                entry.last_used = time;
+            }
+            None => self.entries.push(HistoryEntry {
+                mime: mime.to_owned(),
+                handler: handler.to_owned(),
+                count: 1,
+                last_used: time,
+            }),
+        }
+    }
+
+    // How often and how recently the handler was picked for the mime
+    // Every time it was picked counts less the longer ago it was last picked
+    pub fn frecency(&self, mime: &str, handler: &str) -> f64 {
+        self.frecency_at(mime, handler, now())
+    }
+
+    fn frecency_at(&self, mime: &str, handler: &str, time: u64) -> f64 {
+        self.entries
+            .iter()
+            .find(|e| e.mime == mime && e.handler == handler)
+            .map_or(0.0, |e| {
+                let age = time.saturating_sub(e.last_used) as f64;
+                e.count as f64 * 0.5f64.powf(age / HALF_LIFE)
+            })
+    }
+
+    // The handler picked last for the mime
+    pub fn last(&self, mime: &str) -> Option<&str> {
+        self.entries
+            .iter()
+            .filter(|e| e.mime == mime)
+            .max_by_key(|e| e.last_used)
+            .map(|e| e.handler.as_str())
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |time| time.as_secs())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn history() -> Result<()> {
+        let path = std::env::temp_dir()
+            .join(format!("handlr-history-{}", std::process::id()));
+        let day = 24 * 60 * 60;
+
+        let mut history = History::load_from(path.clone())?;
+        history.record_at("text/html", "brave.desktop", 0);
+        history.record_at("text/html", "firefox.desktop", 90 * day);
+        history.record_at("text/html", "firefox.desktop", 91 * day);
+        history.record_at("text/html", "firefox.desktop", 92 * day);
+        history.record_at("text/html", "chromium.desktop", 200 * day);
+        history.record_at("image/png", "feh.desktop", 300 * day);
+        history.save()?;
+
+        let history = History::load_from(path.clone())?;
+        assert_eq!(history.last("text/html"), Some("chromium.desktop"));
+        assert_eq!(history.last("video/mp4"), None);
+
+        let frecency = |handler, day_| {
+            history.frecency_at("text/html", handler, day_ * day)
+        };
+        // picked more often and more recently
+        assert!(
+            frecency("firefox.desktop", 93) > frecency("brave.desktop", 93)
+        );
+        // picked once, but much more recently
+        assert!(
+            frecency("chromium.desktop", 200)
+                > frecency("firefox.desktop", 200)
+        );
+        assert_eq!(frecency("epiphany.desktop", 200), 0.0);
+
+        fs::remove_file(&path)?;
+        Ok(())
+    }
+}
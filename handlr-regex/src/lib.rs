@@ -3,6 +3,12 @@ pub mod cli;
 pub mod common;
 pub mod config;
 pub mod error;
+pub mod history;
+pub mod multiplexer;
+pub mod picker;
+pub mod project;
+pub mod selector;
+pub mod terminal;
 pub mod utils;
 
 pub use apps::*;
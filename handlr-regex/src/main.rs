@@ -1,22 +1,44 @@
 use clap::Parser;
 use handlr_regex::{
     apps::{self, APPS},
-    cli::Cmd,
+    cli::{Cli, Cmd, ConfigCmd, TerminalCmd},
     common::{self, mime_table},
-    config::CONFIG,
+    config::{Config, CONFIG},
     error::{ErrorKind, Result},
+    terminal::Terminal,
     utils,
 };
 use once_cell::sync::Lazy;
 
 fn main() -> Result<()> {
-    // create config if it doesn't exist
-    Lazy::force(&CONFIG);
+    let Cli { config, command } = Cli::parse();
 
-    let mut apps = (*APPS).clone();
+    if let Some(path) = config {
+        Config::set_path(path);
+    }
 
     let res = || -> Result<()> {
-        match Cmd::parse() {
+        // config subcommands work on the config files themselves,
+        // so they have to run even when the config is invalid
+        let command = match command {
+            Cmd::Config { command } => return run_config(command),
+            command => command,
+        };
+
+        // per-directory handlers only matter when resolving handlers
+        if matches!(
+            command,
+            Cmd::Open { .. } | Cmd::Get { .. } | Cmd::Launch { .. }
+        ) {
+            Config::use_project_layers();
+        }
+
+        // create config if it doesn't exist
+        Lazy::force(&CONFIG);
+
+        let mut apps = (*APPS).clone();
+
+        match command {
             Cmd::Set { mime, handler } => {
                 apps.set_handler(mime.0, handler);
                 apps.save()?;
@@ -25,16 +47,36 @@ fn main() -> Result<()> {
                 apps.add_handler(mime.0, handler);
                 apps.save()?;
             }
-            Cmd::Launch { mime, args } => {
+            Cmd::Launch {
+                mime,
+                args,
+                selector,
+            } => {
+                apps.set_select(selector.enabled());
                 apps.get_handler(&mime.0)?.launch(
                     args.into_iter().map(|a| a.to_string()).collect(),
                 )?;
             }
-            Cmd::Get { mime, json } => {
+            Cmd::Get {
+                mime,
+                json,
+                selector,
+            } => {
+                apps.set_select(selector.enabled());
                 apps.show_handler(&mime.0, json)?;
             }
-            Cmd::Open { paths } => apps.open_paths(&paths)?,
-            Cmd::Mime { paths, json } => {
+            Cmd::Open {
+                paths,
+                with_menu,
+                selector,
+                stdin,
+            } => {
+                let paths = common::with_stdin(paths, stdin.stdin, stdin.null)?;
+                apps.set_select(selector.enabled());
+                apps.open_paths(&paths, with_menu || CONFIG.open_with_menu)?
+            }
+            Cmd::Mime { paths, json, stdin } => {
+                let paths = common::with_stdin(paths, stdin.stdin, stdin.null)?;
                 mime_table(&paths, json)?;
             }
             Cmd::List { all } => {
@@ -43,6 +85,15 @@ fn main() -> Result<()> {
             Cmd::Unset { mime } => {
                 apps.remove_handler(&mime.0)?;
             }
+            // handled before the config is loaded
+            Cmd::Config { .. } => unreachable!(),
+            Cmd::Terminal { command } => match command {
+                TerminalCmd::Get { json } => Terminal::find()?.show(json)?,
+                TerminalCmd::Detect { json } => Terminal::show_detected(json)?,
+                TerminalCmd::Set { terminal } => {
+                    Terminal::set(&mut apps, terminal)?
+                }
+            },
             Cmd::Autocomplete {
                 desktop_files,
                 mimes,
@@ -57,18 +108,31 @@ fn main() -> Result<()> {
         Ok(())
     }();
 
-    match (res, atty::is(atty::Stream::Stdout)) {
-        (Err(e), _) if matches!(*e.kind, ErrorKind::Cancelled) => {
-            std::process::exit(1);
-        }
-        (Err(e), true) => {
-            eprintln!("{}", e);
+    match res {
+        Err(e)
+            if matches!(
+                *e.kind,
+                ErrorKind::Cancelled | ErrorKind::Reported
+            ) =>
+        {
             std::process::exit(1);
         }
-        (Err(e), false) => {
-            utils::notify("handlr error", &e.to_string())?;
+        Err(e) => {
+            utils::report(&e.to_string())?;
             std::process::exit(1);
         }
         _ => Ok(()),
     }
 }
+
+fn run_config(command: ConfigCmd) -> Result<()> {
+    match command {
+        ConfigCmd::Path { json } => Config::show_path(json),
+        ConfigCmd::Show { json } => CONFIG.show(json),
+        ConfigCmd::Get { json, key } => CONFIG.show_key(&key, json),
+        ConfigCmd::Set { key, value } => Config::set_key(&key, &value),
+        ConfigCmd::Edit => Config::edit(),
+        ConfigCmd::Trust { path } => Config::trust(path),
+        ConfigCmd::Check { json } => Config::check(json),
+    }
+}
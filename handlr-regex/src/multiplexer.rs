@@ -0,0 +1,172 @@
+use serde::{Deserialize, Serialize};
+
+// Where to open handlers that need a terminal when handlr runs inside a multiplexer
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Deserialize, Serialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum MultiplexerMode {
+    // ignore the multiplexer
+    #[default]
+    Off,
+    // a new window (or tab) of the current session
+    Window,
+    // a new pane of the current window
+    Pane,
+}
+
+// A terminal multiplexer handlr is running inside of
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Multiplexer {
+    Tmux,
+    Zellij,
+    Screen,
+}
+
+impl Multiplexer {
+    // the multiplexer of the current session, if any
+    pub fn detect() -> Option<Self> {
+        Self::detect_with(|name| std::env::var(name).ok())
+    }
+
+    fn detect_with<V: Fn(&str) -> Option<String>>(var: V) -> Option<Self> {
+        let is_set = |name: &str| var(name).is_some_and(|v| !v.is_empty());
+
+        if is_set("TMUX") {
+            Some(Multiplexer::Tmux)
+        } else if is_set("ZELLIJ") {
+            Some(Multiplexer::Zellij)
+        } else if is_set("STY") {
+            Some(Multiplexer::Screen)
+        } else {
+            None
+        }
+    }
+
+    // Command line running `command` in a new window or pane of the session
+    // Returns None if handlers should not be opened in the multiplexer
+    // screen has no panes that can run commands, so it always uses windows
+    pub fn command(
+        &self,
+        mode: MultiplexerMode,
+        title: Option<&str>,
+        command: Vec<String>,
+    ) -> Option<Vec<String>> {
+        let title = title.filter(|t| !t.is_empty());
+        let cwd = std::env::current_dir()
+            .ok()
+            .map(|dir| dir.to_string_lossy().into_owned());
+
+        let mut cmd = match (self, mode) {
+            (_, MultiplexerMode::Off) => return None,
+            (Multiplexer::Tmux, mode) => {
+                let mut cmd = vec![
+                    "tmux".to_owned(),
+                    match mode {
+                        MultiplexerMode::Pane => "split-window",
+                        _ => "new-window",
+                    }
+                    .to_owned(),
+                ];
+                if let Some(cwd) = cwd {
+                    cmd.extend(["-c".to_owned(), cwd]);
+                }
+                if let (Some(title), MultiplexerMode::Window) = (title, mode) {
+                    cmd.extend(["-n".to_owned(), title.to_owned()]);
+                }
+                cmd
+            }
+            // zellij cannot run commands in new tabs, so floating panes
+            // are used as windows instead
+            (Multiplexer::Zellij, mode) => {
+                let mut cmd = vec!["zellij".to_owned(), "run".to_owned()];
+                if mode == MultiplexerMode::Window {
+                    cmd.push("--floating".to_owned());
+                }
+                if let Some(cwd) = cwd {
+                    cmd.extend(["--cwd".to_owned(), cwd]);
+                }
+                if let Some(title) = title {
+                    cmd.extend(["--name".to_owned(), title.to_owned()]);
+                }
+                cmd
+            }
+            (Multiplexer::Screen, _) => {
+                let mut cmd = vec![
+                    "screen".to_owned(),
+                    "-X".to_owned(),
+                    "screen".to_owned(),
+                ];
+                if let Some(title) = title {
+                    cmd.extend(["-t".to_owned(), title.to_owned()]);
+                }
+                cmd
+            }
+        };
+
+        if *self != Multiplexer::Screen {
+            cmd.push("--".to_owned());
+        }
+        cmd.extend(command);
+        Some(cmd)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn multiplexers() {
+        let detect = |vars: &[(&str, &str)]| {
+            let vars = vars.iter().copied().collect::<HashMap<_, _>>();
+            Multiplexer::detect_with(|name| {
+                vars.get(name).map(|v| v.to_string())
+            })
+        };
+
+        assert_eq!(
+            detect(&[("TMUX", "/tmp/tmux-1000/default,1234,0")]),
+            Some(Multiplexer::Tmux)
+        );
+        assert_eq!(detect(&[("ZELLIJ", "0")]), Some(Multiplexer::Zellij));
+        assert_eq!(
+            detect(&[("STY", "1234.pts-0.host")]),
+            Some(Multiplexer::Screen)
+        );
+        assert_eq!(detect(&[("TMUX", "")]), None);
+
+        let command = || vec!["nvim".to_owned(), "notes.md".to_owned()];
+        let cwd = std::env::current_dir()
+            .unwrap()
+            .to_string_lossy()
+            .into_owned();
+
+        assert_eq!(
+            Multiplexer::Tmux.command(MultiplexerMode::Off, None, command()),
+            None
+        );
+        assert_eq!(
+            Multiplexer::Tmux
+                .command(MultiplexerMode::Window, Some("Neovim"), command())
+                .unwrap()
+                .join(" "),
+            format!("tmux new-window -c {} -n Neovim -- nvim notes.md", cwd)
+        );
+        assert_eq!(
+            Multiplexer::Zellij
+                .command(MultiplexerMode::Pane, None, command())
+                .unwrap()
+                .join(" "),
+            format!("zellij run --cwd {} -- nvim notes.md", cwd)
+        );
+        assert_eq!(
+            Multiplexer::Screen
+                .command(MultiplexerMode::Pane, Some("Neovim"), command())
+                .unwrap()
+                .join(" "),
+            "screen -X screen -t Neovim nvim notes.md"
+        );
+    }
+}
@@ -0,0 +1,248 @@
+use crate::{Error, ErrorKind, Result};
+use crossterm::{
+    cursor,
+    event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
+    style::{Attribute, Print, SetAttribute},
+    terminal::{self, ClearType},
+    QueueableCommand,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    io::Write,
+    time::{Duration, Instant},
+};
+
+// When to use the built-in picker instead of the selector program
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Deserialize, Serialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum BuiltinSelector {
+    // when attached to a terminal and the selector cannot be used,
+    // i.e. it is not installed or there is no graphical session
+    #[default]
+    Auto,
+    // whenever attached to a terminal
+    Always,
+    Never,
+}
+
+// Most options shown at once
+const MAX_SHOWN: usize = 10;
+
+// Let the user pick one of `options` in the terminal by typing parts of it
+// Drawn on stderr so stdout can still be used for output
+// The default option is picked if no key is pressed before the timeout
+// Returns the picked option like the selector does, or Cancelled
+pub fn pick(
+    prompt: &str,
+    options: &[String],
+    default: Option<(usize, Duration)>,
+) -> Result<String> {
+    terminal::enable_raw_mode()?;
+    let mut picker = Picker {
+        prompt,
+        options,
+        query: String::new(),
+        selected: default.map_or(0, |(i, _)| i),
+        drawn: 0,
+        default: default.map(|(i, timeout)| (i, Instant::now() + timeout)),
+    };
+    let picked = picker.run();
+    let cleared = picker.clear();
+    terminal::disable_raw_mode()?;
+    cleared?;
+    picked
+}
+
+struct Picker<'a> {
+    prompt: &'a str,
+    options: &'a [String],
+    query: String,
+    // index into the matching options
+    selected: usize,
+    // lines drawn below the prompt
+    drawn: u16,
+    // option picked at the deadline, until a key is pressed
+    default: Option<(usize, Instant)>,
+}
+
+impl Picker<'_> {
+    fn run(&mut self) -> Result<String> {
+        loop {
+            let matches = matches(&self.query, self.options);
+            self.selected = self.selected.min(matches.len().saturating_sub(1));
+            self.draw(&matches)?;
+
+            if let Some((default, deadline)) = self.default {
+                let left = deadline.saturating_duration_since(Instant::now());
+                if !event::poll(left)? {
+                    return Ok(self.options[default].clone());
+                }
+            }
+
+            let key = match event::read()? {
+                Event::Key(
+                    key @ KeyEvent {
+                        kind: KeyEventKind::Press | KeyEventKind::Repeat,
+                        ..
+                    },
+                ) => key,
+                _ => continue,
+            };
+            self.default = None;
+            let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+
+            match key.code {
+                KeyCode::Enter => {
+                    return matches
+                        .get(self.selected)
+                        .map(|option| option.to_string())
+                        .ok_or_else(|| Error::from(ErrorKind::Cancelled))
+                }
+                KeyCode::Esc => return Err(Error::from(ErrorKind::Cancelled)),
+                KeyCode::Char('c' | 'd' | 'g') if ctrl => {
+                    return Err(Error::from(ErrorKind::Cancelled))
+                }
+                KeyCode::Up | KeyCode::BackTab => {
+                    self.selected = self.selected.saturating_sub(1)
+                }
+                KeyCode::Char('p' | 'k') if ctrl => {
+                    self.selected = self.selected.saturating_sub(1)
+                }
+                KeyCode::Down | KeyCode::Tab => self.selected += 1,
+                KeyCode::Char('n' | 'j') if ctrl => self.selected += 1,
+                KeyCode::Char('u') if ctrl => self.query.clear(),
+                KeyCode::Backspace => {
+                    self.query.pop();
+                }
+                KeyCode::Char(c) if !ctrl => {
+                    self.query.push(c);
+                    self.selected = 0;
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn draw(&mut self, matches: &[&String]) -> Result<()> {
+        let mut stderr = std::io::stderr();
+        let width = match terminal::size() {
+            Ok((width, _)) if width > 0 => width as usize,
+            _ => 80,
+        };
+        let line = |text: &str| text.chars().take(width).collect::<String>();
+
+        self.reset(&mut stderr)?;
+        stderr.queue(Print(line(&format!("{}{}", self.prompt, self.query))))?;
+
+        // keep the selected option in view
+        let start = self.selected.saturating_sub(MAX_SHOWN - 1);
+        self.drawn = 0;
+        for (i, option) in
+            matches.iter().enumerate().skip(start).take(MAX_SHOWN)
+        {
+            stderr.queue(Print("\r\n"))?;
+            if i == self.selected {
+                stderr
+                    .queue(SetAttribute(Attribute::Reverse))?
+                    .queue(Print(line(&format!("> {}", option))))?
+                    .queue(SetAttribute(Attribute::Reset))?;
+            } else {
+                stderr.queue(Print(line(&format!("  {}", option))))?;
+            }
+            self.drawn += 1;
+        }
+
+        // put the cursor back at the end of the query
+        if self.drawn > 0 {
+            stderr.queue(cursor::MoveUp(self.drawn))?;
+        }
+        let column = (self.prompt.chars().count() + self.query.chars().count())
+            .min(width.saturating_sub(1));
+        stderr.queue(cursor::MoveToColumn(column as u16))?;
+        stderr.flush()?;
+        Ok(())
+    }
+
+    // move to the start of the prompt and clear everything drawn
+    fn reset(&self, stderr: &mut std::io::Stderr) -> Result<()> {
+        stderr
+            .queue(cursor::MoveToColumn(0))?
+            .queue(terminal::Clear(ClearType::FromCursorDown))?;
+        Ok(())
+    }
+
+    fn clear(&self) -> Result<()> {
+        let mut stderr = std::io::stderr();
+        self.reset(&mut stderr)?;
+        stderr.flush()?;
+        Ok(())
+    }
+}
+
+// Options matching the query, best first
+// Options keep their order when they match equally well
+fn matches<'a>(query: &str, options: &'a [String]) -> Vec<&'a String> {
+    let mut matches = options
+        .iter()
+        .filter_map(|option| Some((score(query, option)?, option)))
+        .collect::<Vec<_>>();
+    matches.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+    matches.into_iter().map(|(_, option)| option).collect()
+}
+
+// How well `option` matches `query`, if the characters of the query appear
+// in it in order, ignoring case
+// Consecutive characters and ones at the start of words score higher
+fn score(query: &str, option: &str) -> Option<i64> {
+    let option = option.to_lowercase().chars().collect::<Vec<_>>();
+    let mut score = 0;
+    let mut next = 0;
+    let mut last: Option<usize> = None;
+
+    for c in query.to_lowercase().chars().filter(|c| !c.is_whitespace()) {
+        let i = next + option[next..].iter().position(|o| *o == c)?;
+
+        score += 1;
+        if last.is_some_and(|last| last + 1 == i) {
+            score += 4;
+        }
+        if i == 0 || !option[i - 1].is_alphanumeric() {
+            score += 2;
+        }
+
+        last = Some(i);
+        next = i + 1;
+    }
+
+    Some(score)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fuzzy_matches() {
+        let options = ["Firefox", "Brave", "Fire Viewer", "Chromium"]
+            .iter()
+            .map(|o| o.to_string())
+            .collect::<Vec<_>>();
+        let names = |query: &str| {
+            matches(query, &options)
+                .into_iter()
+                .map(|o| o.as_str())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            names(""),
+            vec!["Firefox", "Brave", "Fire Viewer", "Chromium"]
+        );
+        assert_eq!(names("fv"), vec!["Fire Viewer"]);
+        assert_eq!(names("fox"), vec!["Firefox"]);
+        assert_eq!(names("re"), vec!["Firefox", "Fire Viewer", "Brave"]);
+        assert!(names("xyz").is_empty());
+    }
+}
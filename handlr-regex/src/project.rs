@@ -0,0 +1,177 @@
+use crate::{utils, Error, ErrorKind, Result};
+use sha2::{Digest, Sha256};
+use std::{
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+};
+use toml::Value;
+
+// Name of per-directory config files
+pub static PROJECT_CONFIG: &str = ".handlr.toml";
+
+// Keys of per-directory config files that are used
+// everything else is ignored so a checkout cannot change e.g. the selector
+static PROJECT_KEYS: &[&str] = &["handlers", "mime_handlers"];
+
+// Per-directory config files in `dir` and its ancestors, farthest first
+pub fn find(dir: &Path) -> Vec<PathBuf> {
+    let mut paths = dir
+        .ancestors()
+        .map(|dir| dir.join(PROJECT_CONFIG))
+        .filter(|path| path.is_file())
+        .collect::<Vec<_>>();
+    paths.reverse();
+    paths
+}
+
+// Config layers from trusted per-directory config files for the current directory
+// The user is asked whether to trust files that are not trusted yet
+pub fn layers() -> Result<Vec<Value>> {
+    let dir = match std::env::current_dir() {
+        Ok(dir) => dir,
+        Err(_) => return Ok(Vec::new()),
+    };
+
+    let mut trusted = TrustList::load()?;
+    let mut layers = Vec::new();
+
+    for path in find(&dir) {
+        let content = fs::read(&path)?;
+
+        if !trusted.contains(&path, &content) {
+            if !utils::confirm(&format!(
+                "Trust handlers from {}?",
+                path.to_string_lossy()
+            )) {
+                eprintln!(
+                    "handlr: ignoring untrusted {}, run `handlr config trust` to trust it",
+                    path.to_string_lossy()
+                );
+                continue;
+            }
+            trusted.add(&path, &content)?;
+        }
+
+        layers.push(parse(&path, &content)?);
+    }
+
+    Ok(layers)
+}
+
+// Trust the per-directory config file at `path` in its current state
+pub fn trust(path: &Path) -> Result<()> {
+    let path = path.canonicalize()?;
+    let content = fs::read(&path)?;
+    parse(&path, &content)?;
+    TrustList::load()?.add(&path, &content)
+}
+
+fn parse(path: &Path, content: &[u8]) -> Result<Value> {
+    let layer = toml::from_slice::<Value>(content)
+        .map_err(|e| ErrorKind::BadConfig(path.to_owned(), e))?;
+
+    Ok(Value::Table(match layer {
+        Value::Table(table) => table
+            .into_iter()
+            .filter(|(key, _)| PROJECT_KEYS.contains(&key.as_str()))
+            .collect(),
+        _ => toml::value::Table::new(),
+    }))
+}
+
+// Hashes of trusted per-directory config files
+// Stored like the output of sha256sum, so changing a file makes it untrusted
+struct TrustList {
+    path: PathBuf,
+    entries: Vec<(String, PathBuf)>,
+}
+
+impl TrustList {
+    fn load() -> Result<Self> {
+        let path = xdg::BaseDirectories::with_prefix("handlr")?
+            .get_data_home()
+            .join("trusted");
+        Self::load_from(path)
+    }
+
+    fn load_from(path: PathBuf) -> Result<Self> {
+        let entries = match fs::read_to_string(&path) {
+            Ok(list) => list
+                .lines()
+                .filter_map(|line| {
+                    let (hash, file) = line.split_once("  ")?;
+                    Some((hash.to_owned(), PathBuf::from(file)))
+                })
+                .collect(),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(Error::from(e)),
+        };
+
+        Ok(Self { path, entries })
+    }
+
+    fn contains(&self, path: &Path, content: &[u8]) -> bool {
+        let hash = hash(content);
+        self.entries.iter().any(|(h, p)| h == &hash && p == path)
+    }
+
+    fn add(&mut self, path: &Path, content: &[u8]) -> Result<()> {
+        self.entries.retain(|(_, p)| p != path);
+        self.entries.push((hash(content), path.to_owned()));
+
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let mut file = fs::File::create(&self.path)?;
+        for (hash, path) in self.entries.iter() {
+            writeln!(file, "{}  {}", hash, path.to_string_lossy())?;
+        }
+        Ok(())
+    }
+}
+
+fn hash(content: &[u8]) -> String {
+    Sha256::digest(content)
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn project_configs() -> Result<()> {
+        let root = std::env::temp_dir()
+            .join(format!("handlr-project-{}", std::process::id()));
+        let nested = root.join("a/b");
+        fs::create_dir_all(&nested)?;
+        let outer = root.join(PROJECT_CONFIG);
+        let inner = nested.join(PROJECT_CONFIG);
+        fs::write(&outer, "selector = 'evil'\n[[handlers]]\nexec = 'outer'\n")?;
+        fs::write(&inner, "")?;
+
+        assert_eq!(find(&nested), vec![outer.clone(), inner.clone()]);
+
+        let content = fs::read(&outer)?;
+        let layer = parse(&outer, &content)?;
+        assert!(layer.get("selector").is_none());
+        assert!(layer.get("handlers").is_some());
+
+        let mut trusted = TrustList::load_from(root.join("trusted"))?;
+        assert!(!trusted.contains(&outer, &content));
+        trusted.add(&outer, &content)?;
+        trusted.add(&inner, b"")?;
+
+        let trusted = TrustList::load_from(root.join("trusted"))?;
+        assert!(trusted.contains(&outer, &content));
+        assert!(trusted.contains(&inner, b""));
+        assert!(!trusted.contains(&outer, b"changed"));
+        assert!(!trusted.contains(&inner, &content));
+
+        fs::remove_dir_all(&root)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,307 @@
+use crate::{
+    history::History,
+    picker::{self, BuiltinSelector},
+    utils, Config, Error, ErrorKind, Result, CONFIG,
+};
+use std::{
+    io::prelude::*,
+    process::{Command, Stdio},
+    sync::mpsc,
+    time::Duration,
+};
+
+// An option offered by the selector
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SelectorEntry {
+    // shown to the user, unique among the entries
+    pub label: String,
+    // icon name or path from the desktop entry
+    pub icon: Option<String>,
+    // remembered in the selector history when picked, if set
+    pub id: Option<String>,
+}
+
+impl SelectorEntry {
+    pub fn new(label: impl Into<String>) -> Self {
+        SelectorEntry {
+            label: label.into(),
+            icon: None,
+            id: None,
+        }
+    }
+}
+
+// What is being opened, for {path} and {mime} in the selector command
+#[derive(Debug, Clone, Default)]
+pub struct SelectorTarget {
+    pub path: Option<String>,
+    pub mime: Option<String>,
+}
+
+// Prompt the user to pick one of the entries
+// Entries picked before for the mime are offered first
+// Returns the index of the picked entry, or Cancelled
+pub fn select(
+    entries: &[SelectorEntry],
+    target: &SelectorTarget,
+) -> Result<usize> {
+    let mut history = match &target.mime {
+        Some(_) if CONFIG.selector_history => Some(History::load()?),
+        _ => None,
+    };
+    let mime = target.mime.as_deref().unwrap_or_default();
+
+    let order = match &history {
+        Some(history) => {
+            order_by_history(entries, |id| history.frecency(mime, id))
+        }
+        None => (0..entries.len()).collect(),
+    };
+    let entries = order
+        .iter()
+        .map(|i| entries[*i].clone())
+        .collect::<Vec<_>>();
+    let labels = entries
+        .iter()
+        .map(|entry| entry.label.replace('\n', " "))
+        .collect::<Vec<_>>();
+
+    // the entry picked last is picked if nothing else is in time
+    let default = history
+        .as_ref()
+        .and_then(|history| history.last(mime))
+        .and_then(|last| {
+            entries.iter().position(|e| e.id.as_deref() == Some(last))
+        })
+        .zip(CONFIG.selector_timeout.map(Duration::from_secs));
+
+    let output = if use_builtin(&CONFIG) {
+        let prompt = match default {
+            Some((i, timeout)) => {
+                format!("Open With ({} in {}s): ", labels[i], timeout.as_secs())
+            }
+            None => "Open With: ".to_owned(),
+        };
+        picker::pick(&prompt, &labels, default)?
+    } else {
+        let output = run_selector(
+            &CONFIG,
+            &selector_input(&entries, CONFIG.selector_icons),
+            target,
+            default.map(|(_, timeout)| timeout),
+        )?;
+        match (output, default) {
+            (Some(output), _) => output,
+            (None, Some((i, _))) => labels[i].clone(),
+            (None, None) => String::new(),
+        }
+    };
+
+    let index = resolve(&labels, &output)
+        .ok_or_else(|| Error::from(ErrorKind::Cancelled))?;
+
+    if let (Some(history), Some(id)) = (&mut history, &entries[index].id) {
+        history.record(mime, id);
+        history.save()?;
+    }
+
+    Ok(order[index])
+}
+
+// Indices of the entries, most often and recently picked first
+// Entries never picked keep their order after the others
+fn order_by_history<F: Fn(&str) -> f64>(
+    entries: &[SelectorEntry],
+    frecency: F,
+) -> Vec<usize> {
+    let scores = entries
+        .iter()
+        .map(|entry| entry.id.as_deref().map_or(0.0, &frecency))
+        .collect::<Vec<_>>();
+    let mut order = (0..entries.len()).collect::<Vec<_>>();
+    order.sort_by(|a, b| scores[*b].total_cmp(&scores[*a]));
+    order
+}
+
+// whether to pick with the built-in picker instead of the selector
+fn use_builtin(config: &Config) -> bool {
+    let in_terminal = atty::is(atty::Stream::Stderr);
+    let selector_works = || {
+        utils::is_graphical()
+            && shlex::split(&config.selector)
+                .and_then(|cmd| cmd.into_iter().next())
+                .is_some_and(|program| utils::in_path(&program))
+    };
+
+    match config.builtin_selector {
+        BuiltinSelector::Auto => in_terminal && !selector_works(),
+        BuiltinSelector::Always => in_terminal,
+        BuiltinSelector::Never => false,
+    }
+}
+
+// Lines sent to the selector
+// With icons, they use the extended dmenu format of rofi: `label\0icon\x1ficon`
+fn selector_input(entries: &[SelectorEntry], icons: bool) -> String {
+    entries
+        .iter()
+        .map(|entry| {
+            let label = entry.label.replace('\n', " ");
+            match (&entry.icon, icons) {
+                (Some(icon), true) => format!("{}\0icon\x1f{}", label, icon),
+                _ => label,
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+// The selector command with {path} and {mime} replaced
+fn selector_command(
+    selector: &str,
+    target: &SelectorTarget,
+) -> Result<Vec<String>> {
+    let command = shlex::split(selector)
+        .filter(|command| !command.is_empty())
+        .ok_or_else(|| ErrorKind::Selector(selector.to_owned()))?;
+
+    Ok(command
+        .into_iter()
+        .map(|arg| {
+            arg.replace("{path}", target.path.as_deref().unwrap_or(""))
+                .replace("{mime}", target.mime.as_deref().unwrap_or(""))
+        })
+        .collect())
+}
+
+// Output of the selector, or None if it did not finish before the timeout
+fn run_selector(
+    config: &Config,
+    input: &str,
+    target: &SelectorTarget,
+    timeout: Option<Duration>,
+) -> Result<Option<String>> {
+    let mut command = selector_command(&config.selector, target)?;
+    let mut process = Command::new(command.remove(0))
+        .args(command)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .map_err(|_| ErrorKind::Selector(config.selector.clone()))?;
+
+    process
+        .stdin
+        .take()
+        .ok_or_else(|| ErrorKind::Selector(config.selector.clone()))?
+        .write_all(input.as_bytes())?;
+
+    let mut stdout = process
+        .stdout
+        .take()
+        .ok_or_else(|| ErrorKind::Selector(config.selector.clone()))?;
+
+    // read in the background so the selector can be closed on timeout
+    let (sender, receiver) = mpsc::channel();
+    std::thread::spawn(move || {
+        let mut output = String::with_capacity(24);
+        let read = stdout.read_to_string(&mut output).map(|_| output);
+        let _ = sender.send(read);
+    });
+
+    let output = match timeout {
+        Some(timeout) => match receiver.recv_timeout(timeout) {
+            Ok(output) => output?,
+            Err(_) => {
+                let _ = process.kill();
+                let _ = process.wait();
+                return Ok(None);
+            }
+        },
+        None => receiver
+            .recv()
+            .map_err(|_| ErrorKind::Selector(config.selector.clone()))??,
+    };
+    process.wait()?;
+
+    Ok(Some(output.trim_end_matches('\n').to_owned()))
+}
+
+// Index of the entry the selector picked
+// Selectors may output the label or, like `rofi -format i`, the index of the entry
+fn resolve(labels: &[String], output: &str) -> Option<usize> {
+    if output.is_empty() {
+        return None;
+    }
+
+    labels.iter().position(|label| label == output).or_else(|| {
+        output
+            .trim()
+            .parse::<usize>()
+            .ok()
+            .filter(|index| *index < labels.len())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn selector_protocol() -> Result<()> {
+        let entries = vec![
+            SelectorEntry {
+                label: String::from("Firefox"),
+                icon: Some(String::from("firefox")),
+                id: None,
+            },
+            SelectorEntry::new("Brave"),
+        ];
+
+        assert_eq!(selector_input(&entries, false), "Firefox\nBrave");
+        assert_eq!(
+            selector_input(&entries, true),
+            "Firefox\0icon\x1ffirefox\nBrave"
+        );
+
+        let labels = vec![String::from("Firefox"), String::from("Brave")];
+        assert_eq!(resolve(&labels, "Brave"), Some(1));
+        assert_eq!(resolve(&labels, "0"), Some(0));
+        assert_eq!(resolve(&labels, "2"), None);
+        assert_eq!(resolve(&labels, "Brav"), None);
+        assert_eq!(resolve(&labels, ""), None);
+
+        let target = SelectorTarget {
+            path: Some(String::from("/tmp/a b.pdf")),
+            mime: Some(String::from("application/pdf")),
+        };
+        assert_eq!(
+            selector_command(
+                "rofi -dmenu -p 'Open {path} ({mime}) with'",
+                &target
+            )?,
+            vec![
+                "rofi",
+                "-dmenu",
+                "-p",
+                "Open /tmp/a b.pdf (application/pdf) with"
+            ]
+        );
+        assert!(selector_command("", &target).is_err());
+
+        let entries = ["firefox", "brave", "chromium", "epiphany"]
+            .iter()
+            .map(|id| SelectorEntry {
+                id: Some(id.to_string()),
+                ..SelectorEntry::new(*id)
+            })
+            .collect::<Vec<_>>();
+        let order = order_by_history(&entries, |id| match id {
+            "chromium" => 3.0,
+            "brave" => 0.5,
+            _ => 0.0,
+        });
+        assert_eq!(order, vec![2, 1, 0, 3]);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,662 @@
+use crate::{
+    apps::{MimeApps, SystemApps, APPS},
+    common::{DesktopEntry, Handler},
+    utils, Config, Error, ErrorKind, Result, CONFIG,
+};
+use mime::Mime;
+use serde::{Deserialize, Serialize};
+use std::{ffi::OsString, fmt::Display, path::Path, str::FromStr};
+
+// How to run a command in a known terminal emulator
+// `{}` in arguments is replaced by the value of the option
+struct Profile {
+    // desktop IDs of the terminal
+    ids: &'static [&'static str],
+    // name of its program
+    program: &'static str,
+    // subcommand starting the terminal, which desktop entries may already have
+    subcommand: Option<&'static str>,
+    // arguments before the command to run
+    exec: &'static [&'static str],
+    // keep the terminal open after the command exits
+    hold: &'static [&'static str],
+    // app ID or window class
+    app_id: &'static [&'static str],
+    title: &'static [&'static str],
+}
+
+static PROFILES: &[Profile] = &[
+    Profile {
+        ids: &["foot.desktop", "org.codeberg.dnkl.foot.desktop"],
+        program: "foot",
+        subcommand: None,
+        exec: &[],
+        hold: &["--hold"],
+        app_id: &["--app-id={}"],
+        title: &["--title={}"],
+    },
+    Profile {
+        ids: &["kitty.desktop"],
+        program: "kitty",
+        subcommand: None,
+        exec: &[],
+        hold: &["--hold"],
+        app_id: &["--class={}"],
+        title: &["--title={}"],
+    },
+    Profile {
+        ids: &["Alacritty.desktop"],
+        program: "alacritty",
+        subcommand: None,
+        exec: &["-e"],
+        hold: &["--hold"],
+        app_id: &["--class", "{}"],
+        title: &["--title", "{}"],
+    },
+    Profile {
+        ids: &["org.wezfurlong.wezterm.desktop"],
+        program: "wezterm",
+        subcommand: Some("start"),
+        exec: &["start", "--"],
+        hold: &[],
+        app_id: &[],
+        title: &[],
+    },
+    Profile {
+        ids: &["com.mitchellh.ghostty.desktop"],
+        program: "ghostty",
+        subcommand: None,
+        exec: &["-e"],
+        hold: &["--wait-after-command"],
+        app_id: &["--class={}"],
+        title: &["--title={}"],
+    },
+    Profile {
+        ids: &["org.gnome.Terminal.desktop"],
+        program: "gnome-terminal",
+        subcommand: None,
+        exec: &["--"],
+        hold: &[],
+        app_id: &[],
+        title: &["--title={}"],
+    },
+    Profile {
+        ids: &["org.gnome.Console.desktop"],
+        program: "kgx",
+        subcommand: None,
+        exec: &["--"],
+        hold: &[],
+        app_id: &[],
+        title: &["--title={}"],
+    },
+    Profile {
+        ids: &["org.kde.konsole.desktop"],
+        program: "konsole",
+        subcommand: None,
+        exec: &["-e"],
+        hold: &["--hold"],
+        app_id: &[],
+        title: &[],
+    },
+    Profile {
+        ids: &["xfce4-terminal.desktop"],
+        program: "xfce4-terminal",
+        subcommand: None,
+        exec: &["-x"],
+        hold: &["--hold"],
+        app_id: &["--class={}"],
+        title: &["--title={}"],
+    },
+    Profile {
+        ids: &["terminator.desktop"],
+        program: "terminator",
+        subcommand: None,
+        exec: &["-x"],
+        hold: &[],
+        app_id: &["--classname={}"],
+        title: &["--title={}"],
+    },
+    Profile {
+        ids: &["com.gexperts.Tilix.desktop"],
+        program: "tilix",
+        subcommand: None,
+        exec: &["-e"],
+        hold: &[],
+        app_id: &[],
+        title: &["--title={}"],
+    },
+    Profile {
+        ids: &["xterm.desktop", "debian-xterm.desktop", "uxterm.desktop"],
+        program: "xterm",
+        subcommand: None,
+        exec: &["-e"],
+        hold: &["-hold"],
+        app_id: &["-class", "{}"],
+        title: &["-T", "{}"],
+    },
+    Profile {
+        ids: &["rxvt-unicode.desktop", "urxvt.desktop"],
+        program: "urxvt",
+        subcommand: None,
+        exec: &["-e"],
+        hold: &["-hold"],
+        app_id: &["-name", "{}"],
+        title: &["-title", "{}"],
+    },
+    Profile {
+        ids: &["st.desktop", "st-256color.desktop"],
+        program: "st",
+        subcommand: None,
+        exec: &["-e"],
+        hold: &[],
+        app_id: &["-c", "{}"],
+        title: &["-t", "{}"],
+    },
+    // https://gitlab.freedesktop.org/terminal-wg/specifications/-/merge_requests/3
+    Profile {
+        ids: &["xdg-terminal-exec"],
+        program: "xdg-terminal-exec",
+        subcommand: None,
+        exec: &[],
+        hold: &["--hold"],
+        app_id: &["--app-id={}"],
+        title: &["--title={}"],
+    },
+];
+
+// Arguments of a terminal emulator, from the `terminals` table of the config
+// Overrides those of known terminals, unset ones are unsupported for other terminals
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct TerminalArgs {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    exec: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    hold: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    app_id: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    title: Option<Vec<String>>,
+}
+
+impl TerminalArgs {
+    fn or(self, other: Self) -> Self {
+        TerminalArgs {
+            exec: self.exec.or(other.exec),
+            hold: self.hold.or(other.hold),
+            app_id: self.app_id.or(other.app_id),
+            title: self.title.or(other.title),
+        }
+    }
+}
+
+impl From<&Profile> for TerminalArgs {
+    fn from(profile: &Profile) -> Self {
+        let args =
+            |args: &[&str]| Some(args.iter().map(|a| a.to_string()).collect());
+        TerminalArgs {
+            exec: args(profile.exec),
+            hold: args(profile.hold),
+            app_id: args(profile.app_id),
+            title: args(profile.title),
+        }
+    }
+}
+
+// Options for running a command in a terminal
+#[derive(Debug, Clone, Default)]
+pub struct TerminalOptions<'a> {
+    pub hold: bool,
+    pub app_id: Option<&'a str>,
+    pub title: Option<&'a str>,
+}
+
+// A terminal emulator to run commands in
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Terminal {
+    // desktop ID, or the program for terminals without a desktop entry
+    id: String,
+    // command starting the terminal
+    exec: Vec<String>,
+    args: TerminalArgs,
+    source: Source,
+}
+
+// Where the terminal to use was found
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Source {
+    Default,
+    Env,
+    List,
+    XdgTerminalExec,
+    Detected,
+}
+
+impl Display for Source {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Source::Default => "x-scheme-handler/terminal",
+            Source::Env => "$TERMINAL",
+            Source::List => "xdg-terminals.list",
+            Source::XdgTerminalExec => "xdg-terminal-exec",
+            Source::Detected => "detected",
+        })
+    }
+}
+
+fn terminal_mime() -> Mime {
+    Mime::from_str("x-scheme-handler/terminal").unwrap()
+}
+
+// sort key of a terminal by desktop ID, see `Terminal::detect`
+fn rank(id: &str, listed: &[String]) -> (usize, usize, String) {
+    (
+        listed.iter().position(|l| l == id).unwrap_or(usize::MAX),
+        PROFILES
+            .iter()
+            .position(|p| p.ids.contains(&id))
+            .unwrap_or(usize::MAX),
+        id.to_owned(),
+    )
+}
+
+impl Terminal {
+    fn new(id: String, exec: &str, source: Source) -> Self {
+        Self::with_config(id, exec, source, &CONFIG)
+    }
+
+    fn with_config(
+        id: String,
+        exec: &str,
+        source: Source,
+        config: &Config,
+    ) -> Self {
+        let mut exec = shlex::split(exec)
+            .unwrap_or_default()
+            .into_iter()
+            // field codes make no sense when starting a terminal
+            .filter(|arg| !(arg.len() == 2 && arg.starts_with('%')))
+            .collect::<Vec<_>>();
+
+        let program = exec
+            .first()
+            .and_then(|program| Path::new(program).file_name())
+            .map(|program| program.to_string_lossy().into_owned())
+            .unwrap_or_default();
+
+        let profile = PROFILES
+            .iter()
+            .find(|p| p.ids.contains(&id.as_str()))
+            .or_else(|| PROFILES.iter().find(|p| p.program == program));
+
+        // the profile's arguments start the subcommand again,
+        // so the entry's own, i.e. `wezterm start --cwd .`, is dropped
+        if let Some(subcommand) = profile.and_then(|p| p.subcommand) {
+            if exec.get(1).is_some_and(|arg| arg == subcommand) {
+                exec.truncate(1);
+            }
+        }
+
+        // term_exec_args is used for terminals without a profile,
+        // and for every terminal when changed from its default
+        let exec_args = config
+            .term_exec_args
+            .as_deref()
+            .map(|args| shlex::split(args).unwrap_or_default());
+        let custom_exec_args = exec_args.clone().filter(|_| {
+            config.term_exec_args != Config::default().term_exec_args
+        });
+
+        let profile = match profile {
+            Some(profile) => TerminalArgs {
+                exec: custom_exec_args,
+                ..Default::default()
+            }
+            .or(TerminalArgs::from(profile)),
+            None => TerminalArgs {
+                exec: exec_args,
+                ..Default::default()
+            },
+        };
+
+        let overrides = config
+            .terminals
+            .get(&id)
+            .or_else(|| config.terminals.get(&program))
+            .cloned()
+            .unwrap_or_default();
+
+        Terminal {
+            id,
+            exec,
+            args: overrides.or(profile),
+            source,
+        }
+    }
+
+    fn from_entry(entry: DesktopEntry, source: Source) -> Self {
+        Self::new(
+            entry.file_name.to_string_lossy().into_owned(),
+            &entry.exec,
+            source,
+        )
+    }
+
+    // Find the terminal to use, in this order:
+    // 1. the handler of x-scheme-handler/terminal
+    // 2. $TERMINAL, if env_fallbacks is enabled
+    // 3. the first installed terminal in xdg-terminals.list
+    // 4. xdg-terminal-exec
+    // 5. the best installed terminal, see `detect`
+    // Nothing is changed, use `handlr terminal set` to keep the terminal
+    pub fn find() -> Result<Self> {
+        let terminal_entry = APPS
+            .get_handler(&terminal_mime())
+            .ok()
+            .and_then(|h| h.get_entry().ok());
+
+        if let Some(entry) = terminal_entry {
+            return Ok(Self::from_entry(entry, Source::Default));
+        }
+
+        if let Some(exec) = std::env::var("TERMINAL")
+            .ok()
+            .filter(|t| CONFIG.env_fallbacks && !t.trim().is_empty())
+        {
+            return Ok(Self::new(String::new(), &exec, Source::Env));
+        }
+
+        if let Some(entry) = listed_terminals()
+            .into_iter()
+            .find_map(|id| Handler::from_str(&id).ok()?.get_entry().ok())
+        {
+            return Ok(Self::from_entry(entry, Source::List));
+        }
+
+        if utils::in_path("xdg-terminal-exec") {
+            return Ok(Self::new(
+                "xdg-terminal-exec".into(),
+                "xdg-terminal-exec",
+                Source::XdgTerminalExec,
+            ));
+        }
+
+        Self::detect()?
+            .into_iter()
+            .next()
+            .map(|(_, entry)| Self::from_entry(entry, Source::Detected))
+            .ok_or_else(|| Error::from(ErrorKind::NoTerminal))
+    }
+
+    // Installed terminal emulators, best first:
+    // those in xdg-terminals.list in its order, then known ones in the order of PROFILES,
+    // then the rest by desktop ID
+    pub fn detect() -> Result<Vec<(OsString, DesktopEntry)>> {
+        let listed = listed_terminals();
+        let mut entries = SystemApps::get_entries()?
+            .filter(|(_, entry)| {
+                entry.categories.contains_key("TerminalEmulator")
+            })
+            .collect::<Vec<_>>();
+        entries
+            .sort_by_cached_key(|(id, _)| rank(&id.to_string_lossy(), &listed));
+        Ok(entries)
+    }
+
+    pub fn show(&self, output_json: bool) -> Result<()> {
+        let name = if self.id.is_empty() {
+            self.exec.join(" ")
+        } else {
+            self.id.clone()
+        };
+
+        let output = if output_json {
+            (json::object! {
+                terminal: name,
+                source: self.source.to_string(),
+                cmd: self.command(&TerminalOptions::default(), Vec::new()).join(" "),
+            })
+            .to_string()
+        } else {
+            format!("{} ({})", name, self.source)
+        };
+        println!("{}", output);
+        Ok(())
+    }
+
+    pub fn show_detected(output_json: bool) -> Result<()> {
+        let terminals = Self::detect()?;
+
+        if output_json {
+            let terminals = terminals
+                .iter()
+                .map(|(id, entry)| {
+                    json::object! {
+                        terminal: id.to_string_lossy().into_owned(),
+                        name: entry.name.as_str(),
+                    }
+                })
+                .collect::<Vec<_>>();
+            println!("{}", json::stringify(terminals));
+        } else {
+            terminals
+                .iter()
+                .for_each(|(id, _)| println!("{}", id.to_string_lossy()));
+        }
+        Ok(())
+    }
+
+    // Keep a terminal as the handler of x-scheme-handler/terminal
+    // defaults to the best detected terminal
+    pub fn set(apps: &mut MimeApps, terminal: Option<Handler>) -> Result<()> {
+        let terminal = match terminal {
+            Some(terminal) => terminal,
+            None => Self::detect()?
+                .into_iter()
+                .next()
+                .map(|(id, _)| Handler::assume_valid(id))
+                .ok_or_else(|| Error::from(ErrorKind::NoTerminal))?,
+        };
+
+        apps.set_handler(terminal_mime(), terminal);
+        apps.save()
+    }
+
+    // Command line running `command` in this terminal
+    // Options the terminal does not support are left out
+    pub fn command(
+        &self,
+        options: &TerminalOptions,
+        command: Vec<String>,
+    ) -> Vec<String> {
+        let args = |args: &Option<Vec<String>>, value: &str| {
+            args.iter()
+                .flatten()
+                .map(|arg| arg.replace("{}", value))
+                .collect::<Vec<_>>()
+        };
+
+        let mut cmd = self.exec.clone();
+        if options.hold {
+            cmd.extend(args(&self.args.hold, ""));
+        }
+        if let Some(app_id) = options.app_id {
+            cmd.extend(args(&self.args.app_id, app_id));
+        }
+        if let Some(title) = options.title {
+            cmd.extend(args(&self.args.title, title));
+        }
+        cmd.extend(args(&self.args.exec, ""));
+        cmd.extend(command);
+        cmd
+    }
+}
+
+// Desktop IDs of the preferred terminals from xdg-terminals.list files
+// https://gitlab.freedesktop.org/terminal-wg/specifications/-/merge_requests/3
+fn listed_terminals() -> Vec<String> {
+    let dirs = match xdg::BaseDirectories::new() {
+        Ok(dirs) => dirs,
+        Err(_) => return Vec::new(),
+    };
+
+    let desktops = std::env::var("XDG_CURRENT_DESKTOP")
+        .unwrap_or_default()
+        .split(':')
+        .filter(|d| !d.is_empty())
+        .map(|d| format!("{}-xdg-terminals.list", d.to_lowercase()))
+        .collect::<Vec<_>>();
+
+    std::iter::once(dirs.get_config_home())
+        .chain(dirs.get_config_dirs())
+        .flat_map(|dir| {
+            desktops
+                .iter()
+                .map(String::as_str)
+                .chain(std::iter::once("xdg-terminals.list"))
+                .map(move |file| dir.join(file))
+                .collect::<Vec<_>>()
+        })
+        .filter_map(|path| std::fs::read_to_string(path).ok())
+        .flat_map(|list| parse_terminals_list(&list))
+        .collect()
+}
+
+fn parse_terminals_list(list: &str) -> Vec<String> {
+    list.lines()
+        .map(str::trim)
+        // exclusions and exec args only matter to xdg-terminal-exec itself
+        .filter(|line| {
+            !line.is_empty()
+                && !line.starts_with('#')
+                && !line.starts_with('-')
+                && !line.starts_with('/')
+        })
+        .map(|line| line.trim_start_matches('+'))
+        // desktop actions are not supported
+        .map(|line| line.split(':').next().unwrap_or(line).to_owned())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn terminal_commands() {
+        let terminal = |id: &str, exec: &str, args: TerminalArgs| {
+            let profile = PROFILES
+                .iter()
+                .find(|p| p.ids.contains(&id))
+                .map(TerminalArgs::from)
+                .unwrap_or_default();
+            Terminal {
+                id: id.to_owned(),
+                exec: shlex::split(exec).unwrap(),
+                args: args.or(profile),
+                source: Source::Default,
+            }
+        };
+        let options = TerminalOptions {
+            hold: true,
+            app_id: Some("nvim"),
+            title: Some("Neovim"),
+        };
+        let command = || vec!["nvim".to_owned(), "notes.md".to_owned()];
+
+        assert_eq!(
+            terminal("foot.desktop", "foot", TerminalArgs::default())
+                .command(&options, command())
+                .join(" "),
+            "foot --hold --app-id=nvim --title=Neovim nvim notes.md"
+        );
+        assert_eq!(
+            terminal(
+                "org.gnome.Terminal.desktop",
+                "gnome-terminal",
+                TerminalArgs::default()
+            )
+            .command(&options, command())
+            .join(" "),
+            "gnome-terminal --title=Neovim -- nvim notes.md"
+        );
+        assert_eq!(
+            terminal(
+                "kitty.desktop",
+                "kitty",
+                TerminalArgs {
+                    exec: Some(vec!["--single-instance".to_owned()]),
+                    hold: Some(Vec::new()),
+                    ..Default::default()
+                }
+            )
+            .command(&TerminalOptions::default(), command())
+            .join(" "),
+            "kitty --single-instance nvim notes.md"
+        );
+    }
+
+    #[test]
+    fn terminal_entries() {
+        let command = |exec: &str, config: &Config| {
+            Terminal::with_config(
+                "org.wezfurlong.wezterm.desktop".into(),
+                exec,
+                Source::Default,
+                config,
+            )
+            .command(&TerminalOptions::default(), vec!["nvim".to_owned()])
+            .join(" ")
+        };
+        let config = Config::default();
+
+        // Exec of the desktop entry shipped with wezterm
+        assert_eq!(
+            command("wezterm start --cwd .", &config),
+            "wezterm start -- nvim"
+        );
+        assert_eq!(command("wezterm", &config), "wezterm start -- nvim");
+
+        let config = Config {
+            term_exec_args: Some("start --always-new-process --".into()),
+            ..Default::default()
+        };
+        assert_eq!(
+            command("wezterm start --cwd .", &config),
+            "wezterm start --always-new-process -- nvim"
+        );
+    }
+
+    #[test]
+    fn terminal_ranking() {
+        let listed = vec!["kitty.desktop".to_owned()];
+        let mut ids = vec![
+            "zterm.desktop",
+            "xterm.desktop",
+            "aterm.desktop",
+            "kitty.desktop",
+            "foot.desktop",
+        ];
+        ids.sort_by_key(|id| rank(id, &listed));
+        assert_eq!(
+            ids,
+            vec![
+                "kitty.desktop",
+                "foot.desktop",
+                "xterm.desktop",
+                "aterm.desktop",
+                "zterm.desktop"
+            ]
+        );
+    }
+
+    #[test]
+    fn terminals_list() {
+        assert_eq!(
+            parse_terminals_list(
+                "# comment\n\nfoot.desktop\n+kitty.desktop:new-window\n-xterm.desktop\n/execarg_default\n"
+            ),
+            vec!["foot.desktop", "kitty.desktop"]
+        );
+    }
+}
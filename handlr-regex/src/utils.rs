@@ -1,7 +1,71 @@
 use crate::Result;
+use std::io::{BufRead, BufReader, Write};
+
+// Show a desktop notification
+// Printed to stderr instead when there is no graphical session to show it in
 pub fn notify(title: &str, msg: &str) -> Result<()> {
+    if !is_graphical() {
+        eprintln!("{}: {}", title, msg);
+        return Ok(());
+    }
+
     std::process::Command::new("notify-send")
         .args(["-t", "10000", title, msg])
         .spawn()?;
     Ok(())
 }
+
+// Show an error in the terminal if handlr runs in one, or as a notification
+pub fn report(msg: &str) -> Result<()> {
+    if atty::is(atty::Stream::Stdout) {
+        eprintln!("{}", msg);
+        Ok(())
+    } else {
+        notify("handlr error", msg)
+    }
+}
+
+// Ask the user a yes/no question in the controlling terminal,
+// which also works when stdin is a pipe like with `--stdin`
+// None when there is no terminal to ask in
+pub fn ask(question: &str) -> Option<bool> {
+    let tty = std::fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open("/dev/tty")
+        .ok()?;
+
+    write!(&tty, "{} [y/N] ", question).ok()?;
+    let mut answer = String::new();
+    BufReader::new(&tty).read_line(&mut answer).ok()?;
+    Some(matches!(answer.trim(), "y" | "Y" | "yes" | "Yes"))
+}
+
+// Ask the user a yes/no question, false when there is no one to ask
+pub fn confirm(question: &str) -> bool {
+    ask(question).unwrap_or(false)
+}
+
+// Whether there is a graphical session, as opposed to e.g. SSH or a TTY
+pub fn is_graphical() -> bool {
+    ["DISPLAY", "WAYLAND_DISPLAY"]
+        .iter()
+        .any(|var| std::env::var_os(var).is_some_and(|v| !v.is_empty()))
+}
+
+// Whether programs that need a terminal can run in the current one
+// instead of a new terminal emulator
+pub fn use_current_terminal() -> bool {
+    atty::is(atty::Stream::Stdout)
+}
+
+// Whether a program can be found, either by its path or in $PATH
+pub fn in_path(program: &str) -> bool {
+    if program.contains('/') {
+        return std::path::Path::new(program).is_file();
+    }
+
+    std::env::var_os("PATH").is_some_and(|paths| {
+        std::env::split_paths(&paths).any(|dir| dir.join(program).is_file())
+    })
+}
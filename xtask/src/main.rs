@@ -1,5 +1,5 @@
 use clap::{CommandFactory, Parser};
-use handlr_regex::Cmd;
+use handlr_regex::Cli;
 use std::{
     env,
     error::Error,
@@ -22,7 +22,7 @@ fn main() -> DynResult {
 fn mangen() -> DynResult {
     eprintln!("Generating man pages");
 
-    let cmd = Cmd::command();
+    let cmd = Cli::command();
     generate_manpage(&cmd)?;
 
     for sub in cmd.get_subcommands() {